@@ -0,0 +1,131 @@
+//! Compares dispatching requests by the first segment of their path, see
+//! `MAX_FIRST_SEGMENTS`, with matching every route in order, run with `cargo bench`.
+#![feature(test)]
+// Every route nests the future of the chain one level deeper.
+#![recursion_limit = "512"]
+
+extern crate test;
+
+use core::{
+    convert::Infallible,
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use low_profile::{
+    ErrorType, InvalidEncoding, PathSegments, Read, Router, Segment, Service, Write,
+};
+use test::Bencher;
+
+/// Reads a single request.
+struct Input<'a>(&'a [u8]);
+
+impl ErrorType for Input<'_> {
+    type Error = Infallible;
+}
+
+impl Read for Input<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = buf.len().min(self.0.len());
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+/// Discards the response.
+struct Sink;
+
+impl ErrorType for Sink {
+    type Error = Infallible;
+}
+
+impl Write for Sink {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+}
+
+/// Hides the first segment of a path, the route is always tried like in a plain chain.
+struct Unfiltered<P>(P);
+
+impl<P: PathSegments> PathSegments for Unfiltered<P> {
+    type Output<'a> = P::Output<'a>;
+
+    const REST: bool = P::REST;
+
+    fn parse<'a>(&self, path: &'a str) -> Option<Self::Output<'a>> {
+        self.0.parse(path)
+    }
+
+    fn try_parse<'a>(&self, path: &'a str) -> Result<Option<Self::Output<'a>>, InvalidEncoding> {
+        self.0.try_parse(path)
+    }
+}
+
+/// Builds a router with a `/{segment}/{id}` route for every segment,
+/// the first segment is registered first and matched last.
+macro_rules! router {
+    ($wrap:expr; $($segment:literal)*) => {
+        Router::<(), _>::new()
+            $(.get($wrap(($segment, u32::segment())), || async { $segment }))*
+    };
+}
+
+macro_rules! routes {
+    ($wrap:expr) => {
+        router!($wrap;
+            "r00" "r01" "r02" "r03" "r04" "r05" "r06" "r07" "r08" "r09"
+            "r10" "r11" "r12" "r13" "r14" "r15" "r16" "r17" "r18" "r19"
+            "r20" "r21" "r22" "r23" "r24" "r25" "r26" "r27" "r28" "r29"
+            "r30" "r31" "r32" "r33" "r34" "r35" "r36" "r37" "r38" "r39"
+            "r40" "r41" "r42" "r43" "r44" "r45" "r46" "r47" "r48" "r49"
+            "r50" "r51" "r52" "r53" "r54" "r55" "r56" "r57" "r58" "r59"
+            "r60" "r61" "r62" "r63"
+        )
+    };
+}
+
+/// Polls the future once, the in memory reader and writer never block.
+fn block_on<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("the request did not complete"),
+    }
+}
+
+fn serve(b: &mut Bencher, service: &impl Service, path: &str) {
+    let request = format!("GET {path} HTTP/1.1\r\nConnection: close\r\n\r\n");
+    b.iter(|| block_on(service.serve(Input(request.as_bytes()), Sink)).unwrap());
+}
+
+#[bench]
+fn first_route_chain(b: &mut Bencher) {
+    serve(b, &routes!(Unfiltered), "/r63/1");
+}
+
+#[bench]
+fn first_route_dispatched(b: &mut Bencher) {
+    serve(b, &routes!(core::convert::identity), "/r63/1");
+}
+
+#[bench]
+fn last_route_chain(b: &mut Bencher) {
+    serve(b, &routes!(Unfiltered), "/r00/1");
+}
+
+#[bench]
+fn last_route_dispatched(b: &mut Bencher) {
+    serve(b, &routes!(core::convert::identity), "/r00/1");
+}
+
+#[bench]
+fn not_found_chain(b: &mut Bencher) {
+    serve(b, &routes!(Unfiltered), "/missing/1");
+}
+
+#[bench]
+fn not_found_dispatched(b: &mut Bencher) {
+    serve(b, &routes!(core::convert::identity), "/missing/1");
+}
@@ -1,7 +1,7 @@
 use std::{net::Ipv4Addr, rc::Rc};
 
 use embedded_io_adapters::tokio_1::FromTokio;
-use low_profile::{alloc, extract::Path, heapless::Json, Segment, Service};
+use low_profile::{alloc, extract::Path, heapless::Json, http::StatusCode, Service};
 use tokio::task::LocalSet;
 
 #[derive(serde::Deserialize)]
@@ -19,9 +19,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let router = low_profile::Router::new()
         .get("/", || async { "hello world" })
         .post("/", |body: heapless::String<3>| async move { body })
+        .get("/headers", || async {
+            (StatusCode::OK, [("X-Powered-By", "low-profile")], "hello")
+        })
         .get(
-            ("param", heapless::String::<3>::segment()),
-            |Path((_, p))| async move { p },
+            low_profile::path!("/param/{p: heapless::String<3>}"),
+            |Path((p,))| async move { p },
         )
         // JSON using `serde-json-core` allocation free.
         .post("/json", |Json(body): Json<Body, 256>| async move {
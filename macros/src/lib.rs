@@ -0,0 +1,231 @@
+//! Procedural macros of `low-profile`, use them through the re-exports of `low-profile`.
+
+use proc_macro::{Delimiter, Group, Span, TokenStream, TokenTree};
+
+/// Implementation of `low_profile::path!`, invoked as `__path!($crate, "/template")`.
+///
+/// Splits the template into its segments, the segments are validated at compile time
+/// by the `const fn` constructors of `Template`.
+#[proc_macro]
+pub fn path(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(output) => output,
+        Err((span, message)) => compile_error(span, message),
+    }
+}
+
+type Error = (Span, &'static str);
+
+fn expand(input: TokenStream) -> Result<TokenStream, Error> {
+    let mut input = input.into_iter();
+
+    let krate: TokenStream = input
+        .by_ref()
+        .take_while(|token| !matches!(token, TokenTree::Punct(punct) if punct.as_char() == ','))
+        .collect();
+
+    let literal = match input.next().map(flatten) {
+        Some(TokenTree::Literal(literal)) => literal,
+        Some(token) => return Err((token.span(), "expected a path template string")),
+        None => return Err((Span::call_site(), "expected a path template string")),
+    };
+    let template = literal.to_string();
+    let template = string_value(&template).ok_or((literal.span(), "expected a string literal"))?;
+    let segments = parse(template).map_err(|message| (literal.span(), message))?;
+
+    let mut types = segments.iter().filter_map(|segment| match segment {
+        Segment::Capture { ty, .. } => Some(*ty),
+        Segment::Literal(_) => None,
+    });
+    let output = match types.clone().collect::<Option<Vec<_>>>() {
+        Some(types) if !types.is_empty() => std::format!(
+            "const {{ __krate::Template::<({},)>::new(&[{}]) }}",
+            types.join(", "),
+            segments_expr(&segments)
+        ),
+        _ if types.any(|ty| ty.is_some()) => {
+            return Err((
+                literal.span(),
+                "either all or none of the captures in a path template must have a type",
+            ))
+        }
+        _ => std::format!(
+            "const {{ __krate::Template::<__krate::Named>::named(&[{}]) }}",
+            segments_expr(&segments)
+        ),
+    };
+
+    let output = output
+        .parse()
+        .map_err(|_| (literal.span(), "invalid capture type in path template"))?;
+    Ok(replace_krate(output, &krate))
+}
+
+/// A segment of a path template.
+#[derive(Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Literal(&'a str),
+    Capture {
+        name: &'a str,
+        rest: bool,
+        ty: Option<&'a str>,
+    },
+}
+
+/// Splits a template like `/users/{id: u32}/files/{*file}` into its segments.
+fn parse(template: &str) -> Result<Vec<Segment<'_>>, &'static str> {
+    let path = template
+        .strip_prefix('/')
+        .ok_or("path template must start with `/`")?;
+
+    path.split('/')
+        .map(|segment| {
+            let Some(capture) = segment.strip_prefix('{') else {
+                return Ok(Segment::Literal(segment));
+            };
+            let capture = capture
+                .strip_suffix('}')
+                .ok_or("unclosed capture in path template")?;
+            let (rest, capture) = match capture.strip_prefix('*') {
+                Some(capture) => (true, capture),
+                None => (false, capture),
+            };
+            let (name, ty) = match capture.split_once(':') {
+                Some((_, ty)) if ty.trim().is_empty() => {
+                    return Err("missing type of capture in path template")
+                }
+                Some((name, ty)) => (name.trim(), Some(ty.trim())),
+                None => (capture.trim(), None),
+            };
+            Ok(Segment::Capture { name, rest, ty })
+        })
+        .collect()
+}
+
+fn segments_expr(segments: &[Segment<'_>]) -> String {
+    let segments = segments.iter().map(|segment| match segment {
+        Segment::Literal(literal) => std::format!("__krate::TemplateSegment::Literal({literal:?})"),
+        Segment::Capture {
+            name, rest: false, ..
+        } => std::format!("__krate::TemplateSegment::Capture({name:?})"),
+        Segment::Capture {
+            name, rest: true, ..
+        } => std::format!("__krate::TemplateSegment::Rest({name:?})"),
+    });
+    segments.collect::<Vec<_>>().join(", ")
+}
+
+/// Returns the value of a string literal without escapes.
+fn string_value(literal: &str) -> Option<&str> {
+    let raw = literal.strip_prefix('r').map(|raw| raw.trim_matches('#'));
+    match raw {
+        Some(raw) => raw.strip_prefix('"')?.strip_suffix('"'),
+        None => literal
+            .strip_prefix('"')?
+            .strip_suffix('"')
+            .filter(|value| !value.contains('\\')),
+    }
+}
+
+/// Literals forwarded by a `macro_rules` macro are wrapped in an invisible group.
+fn flatten(token: TokenTree) -> TokenTree {
+    match token {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::None => {
+            let mut inner = group.stream().into_iter();
+            match (inner.next(), inner.next()) {
+                (Some(token), None) => flatten(token),
+                _ => TokenTree::Group(group),
+            }
+        }
+        token => token,
+    }
+}
+
+/// Replaces the `__krate` placeholder with the path of the `low-profile` crate.
+fn replace_krate(stream: TokenStream, krate: &TokenStream) -> TokenStream {
+    stream
+        .into_iter()
+        .flat_map(|token| match token {
+            TokenTree::Ident(ident) if ident.to_string() == "__krate" => krate.clone(),
+            TokenTree::Group(group) => {
+                let mut replaced =
+                    Group::new(group.delimiter(), replace_krate(group.stream(), krate));
+                replaced.set_span(group.span());
+                TokenTree::Group(replaced).into()
+            }
+            token => token.into(),
+        })
+        .collect()
+}
+
+fn compile_error(span: Span, message: &str) -> TokenStream {
+    let error: TokenStream = std::format!("::core::compile_error!({message:?})")
+        .parse()
+        .unwrap();
+    error
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("/users/{id: u32}/files/{*file: heapless::String<8>}"),
+            Ok(vec![
+                Segment::Literal("users"),
+                Segment::Capture {
+                    name: "id",
+                    rest: false,
+                    ty: Some("u32")
+                },
+                Segment::Literal("files"),
+                Segment::Capture {
+                    name: "file",
+                    rest: true,
+                    ty: Some("heapless::String<8>")
+                },
+            ])
+        );
+        assert_eq!(
+            parse("/{name}/"),
+            Ok(vec![
+                Segment::Capture {
+                    name: "name",
+                    rest: false,
+                    ty: None
+                },
+                Segment::Literal(""),
+            ])
+        );
+        assert_eq!(parse("/"), Ok(vec![Segment::Literal("")]));
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        assert_eq!(parse("users"), Err("path template must start with `/`"));
+        assert_eq!(
+            parse("/users/{id"),
+            Err("unclosed capture in path template")
+        );
+        assert_eq!(
+            parse("/users/{id:}"),
+            Err("missing type of capture in path template")
+        );
+    }
+
+    #[test]
+    fn test_string_value() {
+        assert_eq!(string_value(r#""/users""#), Some("/users"));
+        assert_eq!(string_value(r###"r#"/users"#"###), Some("/users"));
+        assert_eq!(string_value(r#""/us\x65rs""#), None);
+        assert_eq!(string_value("1"), None);
+    }
+}
@@ -7,7 +7,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use crate::{
     either::Either,
     extract::{JsonError, JsonRejection},
-    http::StatusCode,
+    http::{HeaderName, HeaderValue, StatusCode},
     io::Cursor,
     FromRequest, IntoResponse, Read, Request, Response,
 };
@@ -48,6 +48,12 @@ where
                     .map_body(Either::Right);
             }
         };
-        (StatusCode::OK, buf).into_response().map_body(Either::Left)
+
+        let mut response = (StatusCode::OK, buf).into_response();
+        let _ = response.headers.insert(
+            const { HeaderName::from_static("Content-Type") },
+            const { HeaderValue::from_static("application/json") },
+        );
+        response.map_body(Either::Left)
     }
 }
@@ -2,7 +2,11 @@ extern crate alloc;
 
 use alloc::{string::String, vec::Vec};
 
-use crate::{http::StatusCode, io::Cursor, IntoResponse, Response};
+use crate::{
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    io::Cursor,
+    IntoResponse, Response,
+};
 
 impl IntoResponse for Vec<u8> {
     type Body = Cursor<Self>;
@@ -10,6 +14,7 @@ impl IntoResponse for Vec<u8> {
     fn into_response(self) -> Response<Self::Body> {
         Response {
             status_code: StatusCode::OK,
+            headers: HeaderMap::new(),
             body: Cursor::new(self),
         }
     }
@@ -19,8 +24,15 @@ impl IntoResponse for String {
     type Body = Cursor<Self>;
 
     fn into_response(self) -> Response<Self::Body> {
+        let mut headers = HeaderMap::new();
+        let _ = headers.insert(
+            const { HeaderName::from_static("Content-Type") },
+            const { HeaderValue::from_static("text/plain; charset=utf-8") },
+        );
+
         Response {
             status_code: StatusCode::OK,
+            headers,
             body: Cursor::new(self),
         }
     }
@@ -0,0 +1,166 @@
+//! Chunked transfer coding, see [RFC 9112, Section 7.1](https://www.rfc-editor.org/rfc/rfc9112#section-7.1).
+
+use crate::{ErrorType, Write};
+
+/// Writes every buffer passed to [`Write::write`] as a single chunk.
+///
+/// The body must be terminated with [`ChunkedWriter::finish`].
+pub(crate) struct ChunkedWriter<W> {
+    inner: W,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Writes the last chunk and an empty trailer section.
+    pub async fn finish(mut self) -> Result<W, W::Error> {
+        self.inner.write_all(b"0\r\n\r\n").await?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: ErrorType> ErrorType for ChunkedWriter<W> {
+    type Error = W::Error;
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        // A zero sized chunk would terminate the body.
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut size = [0u8; 2 * core::mem::size_of::<usize>() + 2];
+        let size = encode_chunk_size(buf.len(), &mut size);
+
+        self.inner.write_all(size).await?;
+        self.inner.write_all(buf).await?;
+        self.inner.write_all(b"\r\n").await?;
+
+        Ok(buf.len())
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush().await
+    }
+}
+
+/// Encodes `len` as hex followed by a CRLF into the end of `buf`.
+fn encode_chunk_size(mut len: usize, buf: &mut [u8]) -> &[u8] {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    let mut pos = buf.len() - 2;
+    buf[pos..].copy_from_slice(b"\r\n");
+
+    loop {
+        pos -= 1;
+        buf[pos] = HEX[len & 0xF];
+        len >>= 4;
+
+        if len == 0 {
+            break;
+        }
+    }
+
+    &buf[pos..]
+}
+
+/// Incremental decoder for a chunked request body.
+///
+/// The decoder is fed the framing bytes one by one, chunk data is passed
+/// through directly by the caller, see [`ChunkedDecoder::data`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ChunkedDecoder {
+    state: State,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Chunk size, with a flag whether a digit has been seen.
+    Size(usize, bool),
+    /// Chunk extensions are ignored.
+    Extension(usize),
+    SizeLf(usize),
+    Data(usize),
+    DataCr,
+    DataLf,
+    /// Start of a trailer field line or the end of the trailer section.
+    TrailerStart,
+    /// Trailer fields are ignored.
+    Trailer,
+    EndLf,
+    Done,
+    /// A malformed body cannot be recovered from.
+    Invalid,
+}
+
+/// Error returned for a malformed chunked body.
+#[derive(Debug)]
+pub(crate) struct InvalidChunk;
+
+impl ChunkedDecoder {
+    pub fn new() -> Self {
+        Self {
+            state: State::Size(0, false),
+        }
+    }
+
+    /// Returns `true` once the last chunk and the trailer section have been decoded.
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    /// Returns the amount of data left in the current chunk, if the decoder expects data.
+    pub fn data(&self) -> Option<usize> {
+        match self.state {
+            State::Data(remaining) => Some(remaining),
+            _ => None,
+        }
+    }
+
+    /// Consumes `len` bytes of chunk data.
+    pub fn consume_data(&mut self, len: usize) {
+        if let State::Data(remaining) = self.state {
+            self.state = match remaining - len {
+                0 => State::DataCr,
+                remaining => State::Data(remaining),
+            };
+        }
+    }
+
+    /// Decodes a single framing byte.
+    pub fn decode(&mut self, byte: u8) -> Result<(), InvalidChunk> {
+        self.state = match (self.state, byte) {
+            (State::Size(size, _), b'0'..=b'9' | b'a'..=b'f' | b'A'..=b'F') => {
+                let digit = (byte as char).to_digit(16).unwrap() as usize;
+                let size = size
+                    .checked_mul(16)
+                    .and_then(|size| size.checked_add(digit))
+                    .ok_or(InvalidChunk)?;
+                State::Size(size, true)
+            }
+            (State::Size(size, true), b';' | b' ' | b'\t') => State::Extension(size),
+            (State::Size(size, true), b'\r') => State::SizeLf(size),
+            (State::Extension(size), b'\r') => State::SizeLf(size),
+            (State::Extension(size), _) => State::Extension(size),
+            (State::SizeLf(0), b'\n') => State::TrailerStart,
+            (State::SizeLf(size), b'\n') => State::Data(size),
+            (State::DataCr, b'\r') => State::DataLf,
+            (State::DataLf, b'\n') => State::Size(0, false),
+            (State::TrailerStart, b'\r') => State::EndLf,
+            (State::TrailerStart, _) => State::Trailer,
+            (State::Trailer, b'\n') => State::TrailerStart,
+            (State::Trailer, _) => State::Trailer,
+            (State::EndLf, b'\n') => State::Done,
+            _ => State::Invalid,
+        };
+
+        if let State::Invalid = self.state {
+            return Err(InvalidChunk);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use core::mem::MaybeUninit;
+
+use crate::{error::ProtocolError, request::HeaderIndices, IntoResponse, Response};
+
+/// Connection level configuration of a [`Router`](crate::Router) and how request paths
+/// are normalized before they are matched.
+#[derive(Debug, Clone, Copy)]
+pub struct ServerConfig {
+    /// Maximum amount of requests served on a single connection.
+    ///
+    /// The response to the last request is sent with `Connection: close`.
+    /// A value of `1` disables keep-alive.
+    pub max_requests_per_connection: usize,
+    /// Creates the response sent for a malformed request, before the connection is closed.
+    ///
+    /// Defaults to [`ServerConfig::default_error_response`].
+    pub error_response: fn(&ProtocolError) -> Response<&'static [u8]>,
+    /// How a path with a trailing slash is matched, defaults to [`TrailingSlash::Strict`].
+    pub trailing_slash: TrailingSlash,
+    /// Collapses repeated slashes in the request path, `//a///b` is matched as `/a/b`.
+    ///
+    /// The path is collapsed in place, extractors only see the collapsed path.
+    pub merge_slashes: bool,
+}
+
+impl ServerConfig {
+    pub const fn new() -> Self {
+        Self {
+            max_requests_per_connection: 100,
+            error_response: Self::default_error_response,
+            trailing_slash: TrailingSlash::Strict,
+            merge_slashes: false,
+        }
+    }
+
+    /// Responds with the status code of the error and its canonical reason as the body.
+    pub fn default_error_response(err: &ProtocolError) -> Response<&'static [u8]> {
+        let status = err.status_code();
+        (status, status.canonical_reason().unwrap_or_default()).into_response()
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How a request path which does not match any route is handled, if it only differs
+/// from a route by its trailing slash.
+///
+/// The root path `/` is never changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingSlash {
+    /// `/foo` and `/foo/` are different paths.
+    #[default]
+    Strict,
+    /// The request is redirected with `308 Permanent Redirect` to the path with or
+    /// without the trailing slash, if that path matches a route.
+    Redirect,
+    /// A path which does not match any route is matched again with its trailing slash
+    /// removed or added, `/foo/` matches a route for `/foo` and `/foo` a route for `/foo/`.
+    ///
+    /// Without the `alloc` feature, a path with a slash added must fit into
+    /// [`MAX_HEADER_VALUE_LEN`](crate::http::MAX_HEADER_VALUE_LEN) bytes.
+    Ignore,
+}
+
+/// Buffers used to serve a connection, see [`Router::serve_with_buffers`](crate::Router::serve_with_buffers).
+///
+/// - `HEADER`: size of the buffer for the request line and headers, a request
+///   exceeding it is answered with `431 Request Header Fields Too Large`.
+/// - `MAX_HEADERS`: maximum amount of request headers.
+/// - `WRITE`: size of the chunks the response body is read and written in.
+pub struct Buffers<const HEADER: usize, const MAX_HEADERS: usize, const WRITE: usize> {
+    pub(crate) header: [u8; HEADER],
+    pub(crate) headers: [MaybeUninit<HeaderIndices>; MAX_HEADERS],
+    pub(crate) write: [u8; WRITE],
+}
+
+impl<const HEADER: usize, const MAX_HEADERS: usize, const WRITE: usize>
+    Buffers<HEADER, MAX_HEADERS, WRITE>
+{
+    pub const fn new() -> Self {
+        const { assert!(HEADER > 0 && WRITE > 0, "buffers must not be empty") };
+
+        Self {
+            header: [0; HEADER],
+            headers: [const { MaybeUninit::uninit() }; MAX_HEADERS],
+            write: [0; WRITE],
+        }
+    }
+}
+
+impl<const HEADER: usize, const MAX_HEADERS: usize, const WRITE: usize> Default
+    for Buffers<HEADER, MAX_HEADERS, WRITE>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    chunked::ChunkedWriter,
+    http::StatusCode,
+    response::{Body, Response},
+    service::ServiceError,
+    utils::{self, WriteExt},
+    Read, Write,
+};
+
+/// What happens to the connection after a request has been served.
+pub(crate) enum Connection {
+    /// The connection stays open, `pipelined` is the range of the buffer
+    /// which already contains the start of the next request.
+    KeepAlive {
+        pipelined: core::ops::Range<usize>,
+    },
+    Close,
+}
+
+/// Writes the response with the appropriate framing for the client's HTTP `version`.
+///
+/// The response to a `head` request is written with the headers a `GET` request
+/// would receive, but without the body. 1xx, 204 and 304 responses are written
+/// without framing headers and without a body.
+///
+/// Returns whether the connection can be kept alive after the response.
+pub(crate) async fn write_response<B: Body, W: Write>(
+    mut writer: W,
+    buf: &mut [u8],
+    response: Response<B>,
+    version: u8,
+    head: bool,
+    mut keep_alive: bool,
+) -> Result<bool, ServiceError<W::Error, B::Error>> {
+    // Bodies of unknown length are sent chunked, unless the client does not support
+    // it (HTTP/1.0), then the body is delimited by closing the connection.
+    // 1xx, 204 and 304 responses are never framed and their body is omitted like
+    // for a `head` request, see RFC 9110, Section 6.4.1.
+    let headers = response.headers();
+    let has_body = has_body(response.status_code());
+    let mut content_length = None;
+    let mut chunked = false;
+    if has_body
+        && !headers.contains_key("Content-Length")
+        && !headers.contains_key("Transfer-Encoding")
+    {
+        if let Some(len) = response.body.size_hint().exact() {
+            content_length = Some(len);
+        } else if version >= 1 {
+            chunked = true;
+        } else if !head {
+            keep_alive = false;
+        }
+    }
+
+    let send_connection = match headers.get("Connection") {
+        Some(value) => {
+            keep_alive &= !has_token(value.as_str(), "close");
+            false
+        }
+        None => true,
+    };
+
+    write!(writer, "HTTP/1.1 {}\r\n", response.status_code())
+        .await
+        .map_err(write_fmt_error)?;
+    for (name, value) in response.headers().iter() {
+        for part in [name, ": ", value.as_str(), "\r\n"] {
+            writer
+                .write_all(part.as_bytes())
+                .await
+                .map_err(ServiceError::Io)?;
+        }
+    }
+    if let Some(len) = content_length {
+        write!(writer, "Content-Length: {len}\r\n")
+            .await
+            .map_err(write_fmt_error)?;
+    }
+    if chunked {
+        writer
+            .write_all(b"Transfer-Encoding: chunked\r\n")
+            .await
+            .map_err(ServiceError::Io)?;
+    }
+    if send_connection {
+        let connection: &[u8] = match (keep_alive, version) {
+            (false, _) => b"Connection: close\r\n",
+            (true, 0) => b"Connection: keep-alive\r\n",
+            (true, _) => b"",
+        };
+        writer
+            .write_all(connection)
+            .await
+            .map_err(ServiceError::Io)?;
+    }
+    writer.write_all(b"\r\n").await.map_err(ServiceError::Io)?;
+
+    let body = response.into_body();
+    if head || !has_body {
+        // The body is omitted, but it would have been framed like above.
+    } else if chunked {
+        let mut writer = ChunkedWriter::new(&mut writer);
+        write_body(body, &mut writer, buf).await?;
+        writer.finish().await.map_err(ServiceError::Io)?;
+    } else {
+        write_body(body, &mut writer, buf).await?;
+    }
+    writer.flush().await.map_err(ServiceError::Io)?;
+
+    Ok(keep_alive)
+}
+
+async fn write_body<B: Read, W: Write>(
+    mut body: B,
+    mut writer: W,
+    buf: &mut [u8],
+) -> Result<(), ServiceError<W::Error, B::Error>> {
+    loop {
+        let len = body.read(buf).await.map_err(ServiceError::Body)?;
+        if len == 0 {
+            break;
+        }
+        writer
+            .write_all(&buf[..len])
+            .await
+            .map_err(ServiceError::Io)?;
+    }
+
+    Ok(())
+}
+
+/// Informational (1xx), 204 No Content and 304 Not Modified responses never have a body.
+fn has_body(status: StatusCode) -> bool {
+    !status.is_informational()
+        && status != StatusCode::NO_CONTENT
+        && status != StatusCode::NOT_MODIFIED
+}
+
+/// Checks whether a comma separated header value contains `token`.
+pub(crate) fn has_token(value: &str, token: &str) -> bool {
+    value
+        .split(',')
+        .any(|item| item.trim().eq_ignore_ascii_case(token))
+}
+
+fn write_fmt_error<IO, B>(err: utils::WriteFmtError<IO>) -> ServiceError<IO, B> {
+    match err {
+        utils::WriteFmtError::FmtError => unreachable!("internal format buffer too small"),
+        utils::WriteFmtError::Other(err) => ServiceError::Io(err),
+    }
+}
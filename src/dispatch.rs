@@ -0,0 +1,155 @@
+//! Dispatch of requests to the routes of a chain by the first segment of their path.
+//!
+//! The table is backed by a fixed capacity [`heapless::Vec`], with the `alloc`
+//! feature it uses a growable `Vec` instead.
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use crate::{
+    percent::{self, MAX_DECODED_SEGMENT_LEN},
+    route::Route,
+};
+
+/// Maximum amount of distinct first segments a router dispatches on without the `alloc`
+/// feature, the routes for further segments are tried for every request.
+pub const MAX_FIRST_SEGMENTS: usize = 32;
+
+#[cfg(not(feature = "alloc"))]
+type Segments = heapless::Vec<(&'static str, Candidates), MAX_FIRST_SEGMENTS>;
+#[cfg(feature = "alloc")]
+type Segments = alloc::vec::Vec<(&'static str, Candidates)>;
+
+/// The routes of a chain which may match a request.
+///
+/// Bit `i` stands for the `i`-th route counted from the newest one, the last bit
+/// stands for all routes from the 64th on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Candidates(u64);
+
+impl Candidates {
+    pub(crate) const ALL: Self = Self(u64::MAX);
+    const NONE: Self = Self(0);
+
+    fn of(index: usize) -> Self {
+        Self(1 << index.min(63))
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Whether one of the first `len` routes is a candidate.
+    pub(crate) fn any_of_first(self, len: usize) -> bool {
+        match len {
+            0 => false,
+            64.. => self.0 != 0,
+            len => self.0 & ((1 << len) - 1) != 0,
+        }
+    }
+
+    /// The candidates among the routes after the first `len`.
+    pub(crate) fn skip(self, len: usize) -> Self {
+        // The last bit is shifted in, it still stands for all older routes.
+        Self(((self.0 as i64) >> len.min(63)) as u64)
+    }
+}
+
+/// The routes of a chain by the first segment of the paths they match, built when
+/// a route is added to a router.
+///
+/// A request looks up its first segment once, see [`Candidates`], instead of parsing
+/// the path of every route in the chain.
+pub(crate) struct RouteTable {
+    /// Sorted by segment, with the routes which only match paths starting with it.
+    segments: Segments,
+    /// The routes which may match any first segment.
+    any: Candidates,
+}
+
+impl RouteTable {
+    pub(crate) fn new<S, P>(chain: &impl Route<S, P>) -> Self {
+        let mut table = Self {
+            segments: Segments::new(),
+            any: Candidates::NONE,
+        };
+        chain.first_segments(0, &mut |index, segment| table.insert(index, segment));
+        table
+    }
+
+    fn insert(&mut self, index: usize, segment: Option<&'static str>) {
+        let route = Candidates::of(index);
+        let Some(segment) = segment else {
+            self.any = self.any.union(route);
+            return;
+        };
+
+        match self
+            .segments
+            .binary_search_by(|(other, _)| other.cmp(&segment))
+        {
+            Ok(i) => self.segments[i].1 = self.segments[i].1.union(route),
+            Err(i) => {
+                if !self.insert_at(i, segment, route) {
+                    self.any = self.any.union(route);
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn insert_at(&mut self, i: usize, segment: &'static str, route: Candidates) -> bool {
+        self.segments.insert(i, (segment, route)).is_ok()
+    }
+
+    #[cfg(feature = "alloc")]
+    fn insert_at(&mut self, i: usize, segment: &'static str, route: Candidates) -> bool {
+        self.segments.insert(i, (segment, route));
+        true
+    }
+
+    /// The routes which may match the request `path`.
+    ///
+    /// Paths which do not start with a `/` or whose first segment cannot be decoded
+    /// are matched against every route.
+    pub(crate) fn candidates(&self, path: &str) -> Candidates {
+        let Some(path) = path.strip_prefix('/') else {
+            return Candidates::ALL;
+        };
+        let segment = path.split_once('/').map_or(path, |(segment, _)| segment);
+        let mut buf = [0; MAX_DECODED_SEGMENT_LEN];
+        let Ok(segment) = percent::decode_segment(segment, &mut buf) else {
+            return Candidates::ALL;
+        };
+
+        match self
+            .segments
+            .binary_search_by(|(other, _)| (*other).cmp(segment))
+        {
+            Ok(i) => self.segments[i].1.union(self.any),
+            Err(_) => self.any,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let candidates = Candidates::of(1).union(Candidates::of(3));
+        assert!(!candidates.any_of_first(1));
+        assert!(candidates.any_of_first(2));
+        assert_eq!(candidates.skip(2), Candidates::of(1));
+        assert!(!candidates.skip(4).any_of_first(64));
+
+        // The last bit stands for all older routes.
+        let old = Candidates::of(100);
+        assert_eq!(old, Candidates::of(63));
+        assert!(old.skip(1).any_of_first(63));
+        assert!(!old.skip(1).any_of_first(62));
+        assert_eq!(old.skip(200), Candidates::ALL);
+        assert!(Candidates::ALL.any_of_first(64));
+    }
+}
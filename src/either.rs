@@ -2,7 +2,10 @@ use core::fmt::Debug;
 
 use embedded_io_async::{Error, ErrorKind};
 
-use crate::{ErrorType, IntoResponse, Read, Response};
+use crate::{
+    response::{Body, SizeHint},
+    ErrorType, IntoResponse, Read, Response,
+};
 
 pub enum Either<L, R> {
     Left(L),
@@ -40,6 +43,15 @@ impl<L: Read, R: Read> Read for Either<L, R> {
     }
 }
 
+impl<L: Body, R: Body> Body for Either<L, R> {
+    fn size_hint(&self) -> SizeHint {
+        match self {
+            Either::Left(left) => left.size_hint(),
+            Either::Right(right) => right.size_hint(),
+        }
+    }
+}
+
 impl<L: IntoResponse, R: IntoResponse> IntoResponse for Either<L, R> {
     type Body = Either<L::Body, R::Body>;
 
@@ -1,9 +1,11 @@
-use crate::http::InvalidMethod;
+use crate::http::{InvalidMethod, StatusCode};
 
 #[derive(Debug)]
 pub enum InvalidUrl {
     TooLong,
     InvalidUrlCodePoint,
+    /// The asterisk-form `*` is only allowed for `OPTIONS` requests.
+    AsteriskForm,
 }
 
 #[derive(Debug)]
@@ -11,4 +13,34 @@ pub enum ProtocolError {
     InvalidUrl(InvalidUrl),
     InvalidMethod(InvalidMethod),
     Parser(httparse::Error),
+    /// The request head does not fit into the header buffer.
+    HeadersTooLarge,
+    /// The `Content-Length` header is malformed or repeated with different values.
+    InvalidContentLength,
+    /// The request uses a transfer coding other than chunked.
+    UnsupportedTransferCoding,
+    /// The request contains both `Content-Length` and `Transfer-Encoding`.
+    ConflictingFraming,
+    /// The chunked request body is malformed.
+    InvalidChunk,
+}
+
+impl ProtocolError {
+    /// The status code used to answer a request failing with this error.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidUrl(InvalidUrl::TooLong) => StatusCode::URI_TOO_LONG,
+            Self::Parser(httparse::Error::TooManyHeaders) | Self::HeadersTooLarge => {
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+            }
+            Self::Parser(httparse::Error::Version) => StatusCode::HTTP_VERSION_NOT_SUPPORTED,
+            Self::UnsupportedTransferCoding => StatusCode::NOT_IMPLEMENTED,
+            Self::InvalidUrl(InvalidUrl::InvalidUrlCodePoint | InvalidUrl::AsteriskForm)
+            | Self::InvalidMethod(_)
+            | Self::Parser(_)
+            | Self::InvalidContentLength
+            | Self::ConflictingFraming
+            | Self::InvalidChunk => StatusCode::BAD_REQUEST,
+        }
+    }
 }
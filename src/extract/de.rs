@@ -0,0 +1,348 @@
+//! Deserialization of [`NamedCaptures`] for the [`Path`](super::Path) extractor.
+
+use serde::{
+    de::{self, value::BorrowedStrDeserializer, DeserializeSeed, IntoDeserializer, Visitor},
+    forward_to_deserialize_any, Deserializer,
+};
+
+use super::{FailedToDeserializePath, PathErrorKind};
+use crate::{
+    percent::{self, MAX_DECODED_SEGMENT_LEN},
+    NamedCaptures,
+};
+
+type Result<T> = core::result::Result<T, FailedToDeserializePath>;
+
+/// Deserializes all captures, as a map into a struct or as a sequence into a tuple.
+///
+/// A single capture can also be deserialized into a value directly.
+pub(crate) struct PathDeserializer<'de> {
+    captures: NamedCaptures<'de>,
+}
+
+impl<'de> PathDeserializer<'de> {
+    pub fn new(captures: NamedCaptures<'de>) -> Self {
+        Self { captures }
+    }
+
+    fn single(&self) -> Result<ValueDeserializer<'de>> {
+        let mut iter = self.captures.iter();
+        match (iter.len(), iter.next()) {
+            (1, Some((name, value))) => Ok(ValueDeserializer { name, value }),
+            (got, _) => Err(PathErrorKind::WrongNumberOfParameters { got, expected: 1 }.into()),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for PathDeserializer<'de> {
+    type Error = FailedToDeserializePath;
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_option
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(SeqDeserializer {
+            captures: self.captures.iter(),
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        let got = self.captures.len();
+        if got != len {
+            return Err(PathErrorKind::WrongNumberOfParameters { got, expected: len }.into());
+        }
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(MapDeserializer {
+            captures: self.captures.iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(PathErrorKind::UnsupportedType { name: "identifier" }.into())
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+struct SeqDeserializer<I> {
+    captures: I,
+}
+
+impl<'de, I> de::SeqAccess<'de> for SeqDeserializer<I>
+where
+    I: ExactSizeIterator<Item = (&'static str, &'de str)>,
+{
+    type Error = FailedToDeserializePath;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.captures.next() {
+            Some((name, value)) => seed
+                .deserialize(ValueDeserializer { name, value })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.captures.len())
+    }
+}
+
+struct MapDeserializer<'de, I> {
+    captures: I,
+    value: Option<(&'static str, &'de str)>,
+}
+
+impl<'de, I> de::MapAccess<'de> for MapDeserializer<'de, I>
+where
+    I: ExactSizeIterator<Item = (&'static str, &'de str)>,
+{
+    type Error = FailedToDeserializePath;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.captures.next() {
+            Some((name, value)) => {
+                self.value = Some((name, value));
+                seed.deserialize(BorrowedStrDeserializer::new(name))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        match self.value.take() {
+            Some((name, value)) => seed.deserialize(ValueDeserializer { name, value }),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.captures.len())
+    }
+}
+
+/// Deserializes the percent-decoded value of a single capture.
+struct ValueDeserializer<'de> {
+    name: &'static str,
+    value: &'de str,
+}
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                let mut buf = [0; MAX_DECODED_SEGMENT_LEN];
+                let value = self.decode(&mut buf)?;
+                let value = value.parse::<$ty>().map_err(|_| PathErrorKind::ParseError {
+                    name: self.name,
+                    expected_type: stringify!($ty),
+                })?;
+                visitor.$visit(value)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = FailedToDeserializePath;
+
+    parse_value! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+        deserialize_char => visit_char(char),
+    }
+
+    forward_to_deserialize_any! {
+        str string identifier
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut buf = [0; MAX_DECODED_SEGMENT_LEN];
+        match self.value.contains('%') {
+            true => visitor.visit_str(self.decode(&mut buf)?),
+            false => visitor.visit_borrowed_str(self.value),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut buf = [0; MAX_DECODED_SEGMENT_LEN];
+        match self.value.contains('%') {
+            true => visitor.visit_bytes(self.decode(&mut buf)?.as_bytes()),
+            false => visitor.visit_borrowed_bytes(self.value.as_bytes()),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let mut buf = [0; MAX_DECODED_SEGMENT_LEN];
+        visitor.visit_enum(self.decode(&mut buf)?.into_deserializer())
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(self.unsupported("sequence"))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
+        Err(self.unsupported("tuple"))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value> {
+        Err(self.unsupported(name))
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(self.unsupported("map"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value> {
+        Err(self.unsupported(name))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> ValueDeserializer<'de> {
+    fn decode<'b>(&self, buf: &'b mut [u8; MAX_DECODED_SEGMENT_LEN]) -> Result<&'b str>
+    where
+        'de: 'b,
+    {
+        percent::decode_segment(self.value, buf).map_err(|error| {
+            PathErrorKind::InvalidEncoding {
+                name: self.name,
+                error,
+            }
+            .into()
+        })
+    }
+
+    fn unsupported(&self, name: &'static str) -> FailedToDeserializePath {
+        PathErrorKind::UnsupportedType { name }.into()
+    }
+}
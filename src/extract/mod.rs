@@ -2,6 +2,8 @@ use core::future::Future;
 
 use crate::{request::Parts, IntoResponse, Read, Request};
 
+#[cfg(feature = "serde")]
+mod de;
 mod rejections;
 mod request_parts;
 
@@ -18,20 +20,25 @@ mod private {
 
 pub trait FromRequestParts<'a, S, P>: Sized {
     type Rejection: IntoResponse;
+    /// The value passed to the handler. Differs from `Self` only for extractors which
+    /// borrow from the request, like a [`Path`] of `&str` captures.
+    type Extracted = Self;
 
     fn from_request_parts(
         parts: &mut Parts<'a, P>,
         state: &S,
-    ) -> impl Future<Output = Result<Self, Self::Rejection>>;
+    ) -> impl Future<Output = Result<Self::Extracted, Self::Rejection>>;
 }
 
 pub trait FromRequest<'a, S, P, M = private::ViaRequest>: Sized {
     type Rejection: IntoResponse;
+    /// The value passed to the handler, see [`FromRequestParts::Extracted`].
+    type Extracted = Self;
 
     fn from_request<R: Read>(
         req: Request<'a, R, P>,
         state: &S,
-    ) -> impl Future<Output = Result<Self, Self::Rejection>>;
+    ) -> impl Future<Output = Result<Self::Extracted, Self::Rejection>>;
 }
 
 impl<'a, S, P, T> FromRequest<'a, S, P, private::ViaParts> for T
@@ -39,12 +46,13 @@ where
     T: FromRequestParts<'a, S, P>,
 {
     type Rejection = T::Rejection;
+    type Extracted = T::Extracted;
 
     fn from_request<R: Read>(
         req: Request<'a, R, P>,
         state: &S,
-    ) -> impl Future<Output = Result<Self, Self::Rejection>> {
+    ) -> impl Future<Output = Result<Self::Extracted, Self::Rejection>> {
         let (mut parts, _) = req.into_parts();
-        async move { Self::from_request_parts(&mut parts, state).await }
+        async move { T::from_request_parts(&mut parts, state).await }
     }
 }
@@ -25,6 +25,20 @@ define_rejection! {
     pub struct JsonError;
 }
 
+define_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Missing Host"]
+    /// The request has neither a `Host` header nor an absolute-form target.
+    pub struct MissingHost;
+}
+
+define_rejection! {
+    #[status = REQUEST_HEADER_FIELDS_TOO_LARGE]
+    #[body = "Host too long"]
+    /// The host does not fit into the buffer of the [`Host`](super::Host) extractor.
+    pub struct HostTooLong;
+}
+
 composite_rejection! {
     pub enum VecRejection {
         UnknownBodyError,
@@ -39,9 +53,182 @@ composite_rejection! {
     }
 }
 
+composite_rejection! {
+    pub enum HostRejection {
+        MissingHost,
+        HostTooLong,
+    }
+}
+
 composite_rejection! {
     pub enum JsonRejection {
         VecRejection,
         JsonError,
     }
 }
+
+#[cfg(feature = "serde")]
+pub use self::path::*;
+
+#[cfg(feature = "serde")]
+mod path {
+    use core::fmt::{self, Write};
+
+    use crate::{
+        http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+        io::Cursor,
+        IntoResponse, InvalidEncoding, Response,
+    };
+
+    /// Rejection used by [`Path`](crate::extract::Path) when the captured path segments
+    /// cannot be deserialized.
+    #[derive(Debug)]
+    pub struct FailedToDeserializePath {
+        kind: PathErrorKind,
+    }
+
+    /// Maximum length of a custom [`PathErrorKind::Message`].
+    pub const MAX_PATH_ERROR_MESSAGE_LEN: usize = 64;
+
+    /// The reason the path segments could not be deserialized.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[non_exhaustive]
+    pub enum PathErrorKind {
+        /// The captured segment `name` could not be parsed as `expected_type`.
+        ParseError {
+            name: &'static str,
+            expected_type: &'static str,
+        },
+        /// The captured segment `name` cannot be percent-decoded.
+        InvalidEncoding {
+            name: &'static str,
+            error: InvalidEncoding,
+        },
+        /// The amount of captured segments does not match the deserialized type.
+        WrongNumberOfParameters { got: usize, expected: usize },
+        /// The deserialized type cannot be created from path segments.
+        UnsupportedType { name: &'static str },
+        /// A custom error of the deserialized type, truncated to [`MAX_PATH_ERROR_MESSAGE_LEN`].
+        Message(heapless::String<MAX_PATH_ERROR_MESSAGE_LEN>),
+    }
+
+    impl FailedToDeserializePath {
+        pub fn kind(&self) -> &PathErrorKind {
+            &self.kind
+        }
+
+        /// Invalid segments are a client error, an unsupported type is a server error.
+        pub fn status(&self) -> StatusCode {
+            match self.kind {
+                PathErrorKind::InvalidEncoding { error, .. } => error.status(),
+                PathErrorKind::ParseError { .. } | PathErrorKind::Message(_) => {
+                    StatusCode::BAD_REQUEST
+                }
+                PathErrorKind::WrongNumberOfParameters { .. }
+                | PathErrorKind::UnsupportedType { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        }
+    }
+
+    impl From<PathErrorKind> for FailedToDeserializePath {
+        fn from(kind: PathErrorKind) -> Self {
+            Self { kind }
+        }
+    }
+
+    impl fmt::Display for FailedToDeserializePath {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match &self.kind {
+                PathErrorKind::ParseError {
+                    name,
+                    expected_type,
+                } => write!(f, "Cannot parse `{name}` as `{expected_type}`"),
+                PathErrorKind::InvalidEncoding { name, error } => {
+                    write!(f, "Cannot decode `{name}`: {error}")
+                }
+                PathErrorKind::WrongNumberOfParameters { got, expected } => write!(
+                    f,
+                    "Wrong number of path parameters, expected {expected} but got {got}"
+                ),
+                PathErrorKind::UnsupportedType { name } => {
+                    write!(f, "Unsupported type `{name}` for path parameters")
+                }
+                PathErrorKind::Message(message) => f.write_str(message),
+            }
+        }
+    }
+
+    impl serde::de::StdError for FailedToDeserializePath {}
+
+    impl serde::de::Error for FailedToDeserializePath {
+        fn custom<T: fmt::Display>(msg: T) -> Self {
+            let mut message = heapless::String::new();
+            let _ = write!(Truncate(&mut message), "{msg}");
+            PathErrorKind::Message(message).into()
+        }
+    }
+
+    /// Writes into a string, the part which does not fit is cut off at a char boundary.
+    struct Truncate<'a, const N: usize>(&'a mut heapless::String<N>);
+
+    impl<const N: usize> Write for Truncate<'_, N> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let mut end = s.len().min(N - self.0.len());
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            // Cannot fail, the slice fits.
+            let _ = self.0.push_str(&s[..end]);
+            Ok(())
+        }
+    }
+
+    impl IntoResponse for FailedToDeserializePath {
+        type Body = Cursor<heapless::String<128>>;
+
+        fn into_response(self) -> Response<Self::Body> {
+            let mut body = heapless::String::new();
+            // The longest message fits, custom messages are truncated when they are created.
+            let _ = write!(body, "Invalid URL: {self}");
+
+            let mut headers = HeaderMap::new();
+            let _ = headers.insert(
+                const { HeaderName::from_static("Content-Type") },
+                const { HeaderValue::from_static("text/plain; charset=utf-8") },
+            );
+
+            Response {
+                status_code: self.status(),
+                headers,
+                body: Cursor::new(body),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        extern crate std;
+
+        use serde::de::Error;
+
+        use super::*;
+
+        #[test]
+        fn test_custom_message_is_truncated() {
+            let message = |err: FailedToDeserializePath| match err.kind {
+                PathErrorKind::Message(message) => std::string::String::from(message.as_str()),
+                kind => panic!("unexpected {kind:?}"),
+            };
+
+            let err = FailedToDeserializePath::custom("a".repeat(100));
+            assert_eq!(message(err), "a".repeat(MAX_PATH_ERROR_MESSAGE_LEN));
+
+            // A char is not split, `é` takes two bytes.
+            let err = FailedToDeserializePath::custom(std::format!("a{}", "é".repeat(40)));
+            assert_eq!(message(err), std::format!("a{}", "é".repeat(31)));
+
+            let err = FailedToDeserializePath::custom(format_args!("{} {}", "short", 1));
+            assert_eq!(message(err), "short 1");
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use core::{convert::Infallible, future::Future};
 
 use super::FromRequestParts;
-use crate::{Headers, Parts};
+use crate::{Headers, IntoResponse, Parts};
 
 impl<'a, S, P> FromRequestParts<'a, S, P> for Headers<'a> {
     type Rejection = Infallible;
@@ -14,6 +14,26 @@ impl<'a, S, P> FromRequestParts<'a, S, P> for Headers<'a> {
     }
 }
 
+/// Extracts the host the request is addressed to, including the port, see [`Parts::host`].
+///
+/// The host is copied into a buffer of `N` bytes. Routes can be restricted to a host
+/// with [`Router::host`](crate::Router::host).
+pub struct Host<const N: usize = 64>(pub heapless::String<N>);
+
+impl<'a, S, P, const N: usize> FromRequestParts<'a, S, P> for Host<N> {
+    type Rejection = super::HostRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts<'a, P>,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let host = parts.host().ok_or(super::MissingHost)?;
+        let mut buf = heapless::String::new();
+        buf.push_str(host).map_err(|_| super::HostTooLong)?;
+        Ok(Self(buf))
+    }
+}
+
 pub struct State<S>(pub S);
 
 impl<'a, S, P, T> FromRequestParts<'a, S, P> for State<T>
@@ -41,20 +61,97 @@ impl<T: Clone> FromRef<T> for T {
     }
 }
 
+/// Extracts the values captured by the route's [`PathSegments`](crate::PathSegments).
+///
+/// Borrowed captures, like the `&str` of a [`StrSegment`](crate::StrSegment) or
+/// [`Rest`](crate::Rest), can be taken by a handler as `Path<(&str,)>`.
+///
+/// With the `serde` feature, the segments captured by a [`Template<Named>`](crate::Template)
+/// can be deserialized into any `T: DeserializeOwned`, like a struct with a field
+/// for each capture or a tuple. A segment which fails to parse is rejected with
+/// [`FailedToDeserializePath`](super::FailedToDeserializePath).
 pub struct Path<P>(pub P);
 
-impl<'a, S, P> FromRequestParts<'a, S, P> for Path<P>
+impl<'a, S, P, T> FromRequestParts<'a, S, P> for Path<T>
 where
-    P: Clone,
+    P: PathCaptures<T>,
 {
-    type Rejection = Infallible;
+    type Rejection = P::Rejection;
+    type Extracted = Path<P::Extracted>;
 
     async fn from_request_parts(
         parts: &mut Parts<'a, P>,
         _state: &S,
-    ) -> Result<Self, Self::Rejection> {
-        // TODO: can we pass a reference here, and or use serde? Can the extracted path keep
-        // references into the original path?
-        Ok(Self(parts.extracted_path.clone()))
+    ) -> Result<Self::Extracted, Self::Rejection> {
+        parts.extracted_path.extract().map(Path)
+    }
+}
+
+/// The captures of a route which can be extracted as a [`Path<T>`].
+///
+/// `T` is the type named by the handler. It may differ from the captures in its
+/// lifetimes, a handler taking `Path<(&str,)>` is called with captures which borrow
+/// from the request.
+pub trait PathCaptures<T> {
+    type Rejection: IntoResponse;
+    type Extracted;
+
+    fn extract(&self) -> Result<Self::Extracted, Self::Rejection>;
+}
+
+macro_rules! impl_path_captures {
+    ($($name:ident),*) => {
+        impl<T, $($name),*> PathCaptures<T> for ($($name,)*)
+        where
+            $($name: Clone),*
+        {
+            type Rejection = Infallible;
+            type Extracted = Self;
+
+            fn extract(&self) -> Result<Self, Self::Rejection> {
+                // Borrowed segments, like `StrSegment`, reference the request path and are cheap to clone.
+                Ok(self.clone())
+            }
+        }
+    };
+}
+
+impl_path_captures!();
+impl_path_captures!(T1);
+impl_path_captures!(T1, T2);
+impl_path_captures!(T1, T2, T3);
+impl_path_captures!(T1, T2, T3, T4);
+impl_path_captures!(T1, T2, T3, T4, T5);
+impl_path_captures!(T1, T2, T3, T4, T5, T6);
+impl_path_captures!(T1, T2, T3, T4, T5, T6, T7);
+impl_path_captures!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_path_captures!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_path_captures!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_path_captures!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_path_captures!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+impl_path_captures!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+impl_path_captures!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+impl_path_captures!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+impl_path_captures!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16);
+
+impl<'p, 'x> PathCaptures<crate::NamedCaptures<'x>> for crate::NamedCaptures<'p> {
+    type Rejection = Infallible;
+    type Extracted = Self;
+
+    fn extract(&self) -> Result<Self, Self::Rejection> {
+        Ok(*self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'p, T> PathCaptures<T> for crate::NamedCaptures<'p>
+where
+    T: serde::de::DeserializeOwned,
+{
+    type Rejection = super::FailedToDeserializePath;
+    type Extracted = T;
+
+    fn extract(&self) -> Result<T, Self::Rejection> {
+        T::deserialize(super::de::PathDeserializer::new(*self))
     }
 }
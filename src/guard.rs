@@ -0,0 +1,185 @@
+//! Guards restrict a route to requests with certain headers or query parameters.
+//!
+//! A route is guarded with [`MethodRouter::guard`](crate::MethodRouter::guard). A request
+//! which is not accepted by the guard falls through to the next route, like a request
+//! for another path, for example to a route for the same path with another content type:
+//!
+//! ```
+//! use low_profile::{guard, post, Router};
+//!
+//! let router = Router::<(), _>::new()
+//!     .route("/items", post(|| async { "form" }))
+//!     .route(
+//!         "/items",
+//!         post(|| async { "json" }).guard(guard::content_type("application/json")),
+//!     );
+//! ```
+//!
+//! Closures over the request [`Parts`] are guards as well.
+
+use crate::{http, route::Decision, Parts, Read, Request, Route};
+
+/// Decides whether a guarded route is matched against a request.
+pub trait Guard<P = ()> {
+    fn check(&self, parts: &Parts<'_, P>) -> bool;
+
+    /// Accepts requests accepted by both guards.
+    fn and<G: Guard<P>>(self, other: G) -> And<Self, G>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Accepts requests accepted by either guard.
+    fn or<G: Guard<P>>(self, other: G) -> Or<Self, G>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+}
+
+impl<P, F> Guard<P> for F
+where
+    F: Fn(&Parts<'_, P>) -> bool,
+{
+    fn check(&self, parts: &Parts<'_, P>) -> bool {
+        self(parts)
+    }
+}
+
+/// Accepts requests with a header `name` equal to `value`, see [`header`].
+#[derive(Debug, Clone, Copy)]
+pub struct Header {
+    name: &'static str,
+    value: &'static str,
+}
+
+/// Accepts requests with a header `name` equal to `value`.
+///
+/// Header names are compared case-insensitively, values exactly. Any of the
+/// headers may match, if the header is repeated.
+pub fn header(name: &'static str, value: &'static str) -> Header {
+    Header { name, value }
+}
+
+impl<P> Guard<P> for Header {
+    fn check(&self, parts: &Parts<'_, P>) -> bool {
+        parts
+            .headers
+            .iter()
+            .any(|(name, value)| name.eq_ignore_ascii_case(self.name) && value.trim() == self.value)
+    }
+}
+
+/// Accepts requests with a body of the media type, see [`content_type`].
+#[derive(Debug, Clone, Copy)]
+pub struct ContentType {
+    media_type: &'static str,
+}
+
+/// Accepts requests with a body of the `media_type`, like `application/json`.
+///
+/// Parameters of the `Content-Type`, like the `charset`, are ignored and the media
+/// type is compared case-insensitively.
+pub fn content_type(media_type: &'static str) -> ContentType {
+    ContentType { media_type }
+}
+
+impl<P> Guard<P> for ContentType {
+    fn check(&self, parts: &Parts<'_, P>) -> bool {
+        parts
+            .headers
+            .get_first("Content-Type")
+            .is_some_and(|value| {
+                let media_type = value
+                    .split_once(';')
+                    .map_or(value, |(media_type, _)| media_type);
+                media_type.trim().eq_ignore_ascii_case(self.media_type)
+            })
+    }
+}
+
+/// Accepts requests with a query parameter, see [`query_has`].
+#[derive(Debug, Clone, Copy)]
+pub struct QueryHas {
+    name: &'static str,
+}
+
+/// Accepts requests with the query parameter `name`, with or without a value.
+///
+/// `query_has("debug")` accepts `?debug`, `?debug=1` and `?a=b&debug=`.
+/// The name is compared with the parameter as is, without percent-decoding it.
+pub fn query_has(name: &'static str) -> QueryHas {
+    QueryHas { name }
+}
+
+impl<P> Guard<P> for QueryHas {
+    fn check(&self, parts: &Parts<'_, P>) -> bool {
+        parts.query.is_some_and(|query| {
+            query
+                .split('&')
+                .any(|param| param.split_once('=').map_or(param, |(name, _)| name) == self.name)
+        })
+    }
+}
+
+/// Accepts requests accepted by both guards, see [`Guard::and`].
+#[derive(Debug, Clone, Copy)]
+pub struct And<A, B>(A, B);
+
+impl<P, A: Guard<P>, B: Guard<P>> Guard<P> for And<A, B> {
+    fn check(&self, parts: &Parts<'_, P>) -> bool {
+        self.0.check(parts) && self.1.check(parts)
+    }
+}
+
+/// Accepts requests accepted by either guard, see [`Guard::or`].
+#[derive(Debug, Clone, Copy)]
+pub struct Or<A, B>(A, B);
+
+impl<P, A: Guard<P>, B: Guard<P>> Guard<P> for Or<A, B> {
+    fn check(&self, parts: &Parts<'_, P>) -> bool {
+        self.0.check(parts) || self.1.check(parts)
+    }
+}
+
+/// A route which is only matched against requests accepted by the guard.
+///
+/// Other requests are not matched, they fall through to the next route and the methods
+/// of the guarded route are not allowed for them. Guarded routes never conflict with
+/// other routes, see [`Pattern`](crate::Pattern).
+pub struct Guarded<G, R> {
+    guard: G,
+    route: R,
+}
+
+impl<G, R> Guarded<G, R> {
+    pub fn new(guard: G, route: R) -> Self {
+        Self { guard, route }
+    }
+}
+
+impl<S, P, G: Guard<P>, R: Route<S, P>> Route<S, P> for Guarded<G, R> {
+    type Response = R::Response;
+
+    async fn match_request<'a, Body: Read>(
+        &'a self,
+        req: Request<'a, Body, P>,
+        state: &S,
+    ) -> Decision<'a, Self::Response, Body, P> {
+        match self.guard.check(&req.parts) {
+            true => self.route.match_request(req, state).await,
+            false => Decision::NoMatch(req),
+        }
+    }
+
+    fn allowed_methods(&self, methods: &mut http::MethodSet) {
+        self.route.allowed_methods(methods);
+    }
+
+    fn matches_path(&self, path: &str, parts: &Parts<'_, P>) -> bool {
+        self.guard.check(parts) && self.route.matches_path(path, parts)
+    }
+}
@@ -118,7 +118,6 @@ macro_rules! impl_handler_func {
         impl<
             S,
             P,
-            Fut,
             F,
             Ret,
             M,
@@ -126,8 +125,13 @@ macro_rules! impl_handler_func {
             $last, $last_err,
         > HandlerFunction<S, P, (M, $($ty,)* $last)> for F
         where
-            F: Fn($($ty,)* $last,) -> Fut,
-            Fut: Future<Output = Ret>,
+            // Names the parameter types, the handler is called with the values they extract
+            // which may borrow from the request.
+            F: AsyncFn($($ty,)* $last,) -> Ret,
+            for<'a> F: AsyncFn(
+                $(<$ty as FromRequestParts<'a, S, P>>::Extracted,)*
+                <$last as FromRequest<'a, S, P, M>>::Extracted,
+            ) -> Ret,
             Ret: IntoResponse,
             $($ty: for<'a> FromRequestParts<'a, S, P, Rejection = $ty_err>, $ty_err: IntoResponse,)*
             $last: for<'a> FromRequest<'a, S, P, M, Rejection = $last_err>,
@@ -177,9 +181,9 @@ macro_rules! all_the_tuples {
 
 all_the_tuples!(impl_handler_func);
 
-pub(crate) struct HandlerFunctionHandlerAdapter<FuncParams, Handler> {
-    pub handler: Handler,
-    pub _params: PhantomData<FuncParams>,
+pub struct HandlerFunctionHandlerAdapter<FuncParams, Handler> {
+    pub(crate) handler: Handler,
+    pub(crate) _params: PhantomData<FuncParams>,
 }
 
 impl<S, P, FuncParams, H> Route<S, P> for HandlerFunctionHandlerAdapter<FuncParams, H>
@@ -191,7 +195,7 @@ where
     async fn match_request<'a, Body: Read>(
         &'a self,
         req: Request<'a, Body, P>,
-        state: &'a S,
+        state: &S,
     ) -> crate::route::Decision<'a, Self::Response, Body, P> {
         crate::route::Decision::Match(self.handler.call(req, state).await)
     }
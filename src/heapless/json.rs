@@ -4,7 +4,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use crate::{
     either::Either,
     extract::{JsonError, JsonRejection},
-    http::StatusCode,
+    http::{HeaderName, HeaderValue, StatusCode},
     io::Cursor,
     FromRequest, IntoResponse, Read, Request, Response,
 };
@@ -49,9 +49,12 @@ where
             }
         };
         buffer.truncate(len);
-        // TODO: send headers
-        (StatusCode::OK, buffer)
-            .into_response()
-            .map_body(Either::Left)
+
+        let mut response = (StatusCode::OK, buffer).into_response();
+        let _ = response.headers.insert(
+            const { HeaderName::from_static("Content-Type") },
+            const { HeaderValue::from_static("application/json") },
+        );
+        response.map_body(Either::Left)
     }
 }
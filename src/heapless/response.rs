@@ -1,4 +1,8 @@
-use crate::{http::StatusCode, io::Cursor, IntoResponse, Response};
+use crate::{
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    io::Cursor,
+    IntoResponse, Response,
+};
 
 impl<const SIZE: usize> IntoResponse for heapless::Vec<u8, SIZE> {
     type Body = Cursor<Self>;
@@ -6,6 +10,7 @@ impl<const SIZE: usize> IntoResponse for heapless::Vec<u8, SIZE> {
     fn into_response(self) -> Response<Self::Body> {
         Response {
             status_code: StatusCode::OK,
+            headers: HeaderMap::new(),
             body: Cursor::new(self),
         }
     }
@@ -15,8 +20,15 @@ impl<const SIZE: usize> IntoResponse for heapless::String<SIZE> {
     type Body = Cursor<Self>;
 
     fn into_response(self) -> Response<Self::Body> {
+        let mut headers = HeaderMap::new();
+        let _ = headers.insert(
+            const { HeaderName::from_static("Content-Type") },
+            const { HeaderValue::from_static("text/plain; charset=utf-8") },
+        );
+
         Response {
             status_code: StatusCode::OK,
+            headers,
             body: Cursor::new(self),
         }
     }
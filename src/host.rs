@@ -0,0 +1,213 @@
+//! Matching of the host a request is addressed to, see [`Router::host`](crate::Router::host).
+
+/// A pattern matched against the host of a request, see [`Parts::host`](crate::Parts::host).
+///
+/// - `device.local` matches the host on any port, names are compared case-insensitively.
+/// - `device.local:8080` only matches the host with the port `8080`.
+/// - `*.example.com` matches any subdomain of `example.com`, but not `example.com` itself.
+/// - `*` matches any host.
+///
+/// A trailing dot of a fully qualified name is ignored, in the pattern and in the host.
+/// IPv6 addresses are written in brackets, like `[::1]:8080`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostPattern {
+    pattern: &'static str,
+    /// Length of the name, without the port and a trailing dot.
+    name_len: usize,
+    port: Option<u16>,
+}
+
+impl HostPattern {
+    /// Parses the `pattern`, an invalid pattern fails to compile when used in a `const`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is empty, ends with an empty label, contains a wildcard
+    /// other than as the first label or has an invalid port.
+    pub const fn new(pattern: &'static str) -> Self {
+        let bytes = pattern.as_bytes();
+
+        // The port follows the last `:`, unless it is part of an IPv6 address.
+        let mut name_len = bytes.len();
+        let mut i = bytes.len();
+        while i > 0 {
+            i -= 1;
+            match bytes[i] {
+                b':' => {
+                    name_len = i;
+                    break;
+                }
+                b']' => break,
+                _ => {}
+            }
+        }
+        assert!(name_len > 0, "host pattern must not be empty");
+
+        let mut port = None;
+        if name_len < bytes.len() {
+            assert!(name_len + 1 < bytes.len(), "invalid port in host pattern");
+            let mut value = 0u32;
+            let mut i = name_len + 1;
+            while i < bytes.len() {
+                assert!(bytes[i].is_ascii_digit(), "invalid port in host pattern");
+                value = value * 10 + (bytes[i] - b'0') as u32;
+                assert!(value <= u16::MAX as u32, "invalid port in host pattern");
+                i += 1;
+            }
+            port = Some(value as u16);
+        }
+
+        let ipv6 = bytes[0] == b'[';
+        assert!(
+            !ipv6 || bytes[name_len - 1] == b']',
+            "unclosed IPv6 address in host pattern"
+        );
+        let mut i = 0;
+        while i < name_len {
+            let valid = match bytes[i] {
+                b'*' => {
+                    assert!(
+                        i == 0 && (name_len == 1 || bytes[1] == b'.'),
+                        "a wildcard must be the first label of a host pattern"
+                    );
+                    true
+                }
+                b'[' => i == 0,
+                b']' => ipv6 && i == name_len - 1,
+                b':' => ipv6,
+                b'-' | b'.' | b'_' => true,
+                b => b.is_ascii_alphanumeric(),
+            };
+            assert!(valid, "invalid character in host pattern");
+            i += 1;
+        }
+
+        // The host is compared without its trailing dot.
+        if bytes[name_len - 1] == b'.' {
+            name_len -= 1;
+        }
+        assert!(
+            name_len > 0 && bytes[name_len - 1] != b'.',
+            "empty label in host pattern"
+        );
+
+        Self {
+            pattern,
+            name_len,
+            port,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.pattern
+    }
+
+    /// Whether the `host`, like `device.local:8080`, matches the pattern.
+    pub fn matches(&self, host: &str) -> bool {
+        let Some((name, port)) = split_port(host) else {
+            return false;
+        };
+        if self.port.is_some() && port != self.port {
+            return false;
+        }
+
+        let name = name.strip_suffix('.').unwrap_or(name).as_bytes();
+        let pattern = &self.pattern[..self.name_len];
+        match pattern.strip_prefix('*') {
+            Some("") => !name.is_empty(),
+            // The suffix includes the `.` of the wildcard label.
+            Some(suffix) => {
+                name.len() > suffix.len()
+                    && name[name.len() - suffix.len()..].eq_ignore_ascii_case(suffix.as_bytes())
+            }
+            None => name.eq_ignore_ascii_case(pattern.as_bytes()),
+        }
+    }
+}
+
+/// Splits a host into its name and port, `None` if the port is invalid.
+///
+/// An empty port, like in `device.local:`, is allowed and treated as missing.
+fn split_port(host: &str) -> Option<(&str, Option<u16>)> {
+    match host.rfind(':') {
+        Some(i) if !host[i..].contains(']') => {
+            let (name, port) = (&host[..i], &host[i + 1..]);
+            if port.is_empty() {
+                return Some((name, None));
+            }
+            if !port.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            Some((name, Some(port.parse().ok()?)))
+        }
+        _ => Some((host, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_pattern() {
+        let pattern = HostPattern::new("device.local");
+        assert!(pattern.matches("device.local"));
+        assert!(pattern.matches("Device.Local:8080"));
+        assert!(pattern.matches("device.local."));
+        assert!(pattern.matches("device.local:"));
+        assert!(!pattern.matches("other.local"));
+        assert!(!pattern.matches("sub.device.local"));
+        assert!(!pattern.matches("device.local:http"));
+
+        let pattern = HostPattern::new("device.local:8080");
+        assert!(pattern.matches("device.local:8080"));
+        assert!(!pattern.matches("device.local"));
+        assert!(!pattern.matches("device.local:80"));
+
+        let pattern = HostPattern::new("*.example.com");
+        assert!(pattern.matches("a.example.com"));
+        assert!(pattern.matches("a.b.EXAMPLE.com:443"));
+        assert!(!pattern.matches("example.com"));
+        assert!(!pattern.matches(".example.com"));
+        assert!(!pattern.matches("badexample.com"));
+
+        let pattern = HostPattern::new("*");
+        assert!(pattern.matches("anything:1"));
+
+        let pattern = HostPattern::new("[::1]:8080");
+        assert!(pattern.matches("[::1]:8080"));
+        assert!(!pattern.matches("[::1]"));
+        assert!(HostPattern::new("[::1]").matches("[::1]:80"));
+    }
+
+    #[test]
+    #[should_panic(expected = "a wildcard must be the first label of a host pattern")]
+    fn test_host_pattern_wildcard() {
+        HostPattern::new("device.*.local");
+    }
+
+    #[test]
+    fn test_host_pattern_trailing_dot() {
+        let pattern = HostPattern::new("example.com.");
+        assert!(pattern.matches("example.com"));
+        assert!(pattern.matches("Example.com."));
+        assert!(!pattern.matches("example.com.."));
+
+        let pattern = HostPattern::new("*.example.com.:8080");
+        assert!(pattern.matches("a.example.com:8080"));
+        assert!(pattern.matches("a.example.com.:8080"));
+        assert!(!pattern.matches("a.example.com"));
+    }
+
+    #[test]
+    #[should_panic(expected = "empty label in host pattern")]
+    fn test_host_pattern_empty_label() {
+        HostPattern::new("example.com..");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid port in host pattern")]
+    fn test_host_pattern_port() {
+        HostPattern::new("device.local:99999");
+    }
+}
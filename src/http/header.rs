@@ -0,0 +1,467 @@
+//! HTTP response headers.
+//!
+//! The header map is backed by a fixed capacity [`heapless::Vec`], with the `alloc`
+//! feature it uses a growable `Vec` instead.
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::fmt;
+
+use super::method::is_tchar;
+
+/// Maximum amount of headers a [`HeaderMap`] can hold without the `alloc` feature.
+pub const MAX_RESPONSE_HEADERS: usize = 8;
+
+/// Maximum length of a header value, which is not `'static`, without the `alloc` feature.
+pub const MAX_HEADER_VALUE_LEN: usize = 64;
+
+#[cfg(not(feature = "alloc"))]
+type Entries = heapless::Vec<(&'static str, HeaderValue), MAX_RESPONSE_HEADERS>;
+#[cfg(feature = "alloc")]
+type Entries = alloc::vec::Vec<(&'static str, HeaderValue)>;
+
+/// A collection of response headers.
+///
+/// Header names are compared case-insensitively and written to the client as is.
+///
+/// Without the `alloc` feature the map holds up to [`MAX_RESPONSE_HEADERS`] headers,
+/// inserting into an empty map always succeeds.
+#[derive(Clone, Default)]
+pub struct HeaderMap {
+    entries: Entries,
+}
+
+impl HeaderMap {
+    /// Creates an empty header map.
+    pub const fn new() -> Self {
+        Self {
+            entries: Entries::new(),
+        }
+    }
+
+    /// Returns the amount of headers contained in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map contains no headers.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the first value associated with the header `name`.
+    pub fn get(&self, name: &str) -> Option<&HeaderValue> {
+        self.entries
+            .iter()
+            .find_map(|(key, value)| key.eq_ignore_ascii_case(name).then_some(value))
+    }
+
+    /// Returns `true` if the map contains a header with the `name`.
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Inserts a header, replacing all previous values with the same name.
+    ///
+    /// Returns the first previously associated value.
+    pub fn insert(
+        &mut self,
+        name: HeaderName,
+        value: HeaderValue,
+    ) -> Result<Option<HeaderValue>, TooManyHeaders> {
+        let previous = self.remove(name.as_str());
+        self.push(name.as_str(), value)?;
+
+        Ok(previous)
+    }
+
+    /// Appends a header, keeping all previous values with the same name.
+    pub fn append(&mut self, name: HeaderName, value: HeaderValue) -> Result<(), TooManyHeaders> {
+        self.push(name.as_str(), value)
+    }
+
+    /// Removes all headers with the `name` and returns the first removed value.
+    pub fn remove(&mut self, name: &str) -> Option<HeaderValue> {
+        let mut previous = None;
+
+        let mut i = 0;
+        while i < self.entries.len() {
+            if self.entries[i].0.eq_ignore_ascii_case(name) {
+                let (_, value) = self.entries.remove(i);
+                previous.get_or_insert(value);
+            } else {
+                i += 1;
+            }
+        }
+
+        previous
+    }
+
+    /// Iterates over all headers in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &HeaderValue)> {
+        self.entries.iter().map(|(name, value)| (*name, value))
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn push(&mut self, name: &'static str, value: HeaderValue) -> Result<(), TooManyHeaders> {
+        self.entries
+            .push((name, value))
+            .map_err(|_| TooManyHeaders(()))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn push(&mut self, name: &'static str, value: HeaderValue) -> Result<(), TooManyHeaders> {
+        self.entries.push((name, value));
+        Ok(())
+    }
+}
+
+impl fmt::Debug for HeaderMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// The name of a response header, a valid HTTP token.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct HeaderName(&'static str);
+
+impl HeaderName {
+    /// Creates a header name from a static string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is empty or contains bytes which are not allowed in a token.
+    /// When used in a const context, like `const { HeaderName::from_static("Content-Type") }`,
+    /// this is a compile time error. Use [`TryFrom`] for names which are not known
+    /// at compile time.
+    pub const fn from_static(name: &'static str) -> Self {
+        if !is_token(name.as_bytes()) {
+            panic!("invalid header name");
+        }
+
+        Self(name)
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+const fn is_token(bytes: &[u8]) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        if !is_tchar(bytes[i]) {
+            return false;
+        }
+        i += 1;
+    }
+
+    !bytes.is_empty()
+}
+
+impl TryFrom<&'static str> for HeaderName {
+    type Error = InvalidHeaderName;
+
+    fn try_from(name: &'static str) -> Result<Self, Self::Error> {
+        match is_token(name.as_bytes()) {
+            true => Ok(Self(name)),
+            false => Err(InvalidHeaderName(())),
+        }
+    }
+}
+
+impl AsRef<str> for HeaderName {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for HeaderName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for HeaderName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The value of a response header.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HeaderValue(Inner);
+
+#[derive(Clone, PartialEq, Eq)]
+enum Inner {
+    Static(&'static str),
+    #[cfg(not(feature = "alloc"))]
+    Owned(heapless::String<MAX_HEADER_VALUE_LEN>),
+    #[cfg(feature = "alloc")]
+    Owned(alloc::string::String),
+}
+
+impl HeaderValue {
+    /// Creates a header value from a static string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value contains bytes which are not allowed in a header value.
+    /// When used in a const context, like `const { HeaderValue::from_static("text/plain") }`,
+    /// this is a compile time error. Use [`TryFrom`] for values which are not known
+    /// at compile time.
+    pub const fn from_static(value: &'static str) -> Self {
+        let bytes = value.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if !is_valid(bytes[i]) {
+                panic!("invalid header value");
+            }
+            i += 1;
+        }
+
+        Self(Inner::Static(value))
+    }
+
+    /// Creates a header value by formatting `value`.
+    ///
+    /// Without the `alloc` feature the formatted value must fit
+    /// into [`MAX_HEADER_VALUE_LEN`] bytes.
+    pub fn from_display(value: impl fmt::Display) -> Result<Self, InvalidHeaderValue> {
+        use core::fmt::Write;
+
+        let mut buf = Owned::new();
+        write!(buf, "{value}").map_err(|_| InvalidHeaderValue(()))?;
+
+        if !buf.bytes().all(is_valid) {
+            return Err(InvalidHeaderValue(()));
+        }
+
+        Ok(Self(Inner::Owned(buf)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Inner::Static(value) => value,
+            Inner::Owned(value) => value,
+        }
+    }
+}
+
+#[cfg(not(feature = "alloc"))]
+type Owned = heapless::String<MAX_HEADER_VALUE_LEN>;
+#[cfg(feature = "alloc")]
+type Owned = alloc::string::String;
+
+/// Visible ASCII, space, tab and obs-text are allowed.
+const fn is_valid(b: u8) -> bool {
+    b >= 0x20 && b != 0x7F || b == b'\t'
+}
+
+impl TryFrom<&'static str> for HeaderValue {
+    type Error = InvalidHeaderValue;
+
+    fn try_from(value: &'static str) -> Result<Self, Self::Error> {
+        match value.bytes().all(is_valid) {
+            true => Ok(Self(Inner::Static(value))),
+            false => Err(InvalidHeaderValue(())),
+        }
+    }
+}
+
+impl AsRef<str> for HeaderValue {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq<str> for HeaderValue {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for HeaderValue {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for HeaderValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for HeaderValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A possible error when inserting into a full [`HeaderMap`].
+pub struct TooManyHeaders(());
+
+impl fmt::Debug for TooManyHeaders {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TooManyHeaders").finish()
+    }
+}
+
+impl fmt::Display for TooManyHeaders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("too many response headers")
+    }
+}
+
+/// A possible error when creating a [`HeaderName`] which is not a valid token.
+pub struct InvalidHeaderName(());
+
+impl fmt::Debug for InvalidHeaderName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InvalidHeaderName").finish()
+    }
+}
+
+impl fmt::Display for InvalidHeaderName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid header name")
+    }
+}
+
+/// A possible error when creating a [`HeaderValue`] which is too long
+/// or contains invalid bytes.
+pub struct InvalidHeaderValue(());
+
+impl fmt::Debug for InvalidHeaderValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InvalidHeaderValue").finish()
+    }
+}
+
+impl fmt::Display for InvalidHeaderValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid header value")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert() {
+        let mut headers = HeaderMap::new();
+        assert!(headers.is_empty());
+
+        let previous = headers.insert(
+            HeaderName::from_static("Content-Type"),
+            HeaderValue::from_static("text/plain"),
+        );
+        assert!(previous.unwrap().is_none());
+        headers
+            .append(
+                HeaderName::from_static("Set-Cookie"),
+                HeaderValue::from_static("a=1"),
+            )
+            .unwrap();
+        headers
+            .append(
+                HeaderName::from_static("set-cookie"),
+                HeaderValue::from_static("b=2"),
+            )
+            .unwrap();
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers.get("content-type").unwrap(), "text/plain");
+
+        // Replaces all values and returns the first one.
+        let previous = headers.insert(
+            HeaderName::from_static("SET-COOKIE"),
+            HeaderValue::from_static("c=3"),
+        );
+        assert_eq!(previous.unwrap().unwrap(), "a=1");
+        assert_eq!(headers.len(), 2);
+
+        let mut entries = headers.iter();
+        assert_eq!(
+            entries.next().unwrap(),
+            ("Content-Type", &HeaderValue::from_static("text/plain"))
+        );
+        assert_eq!(
+            entries.next().unwrap(),
+            ("SET-COOKIE", &HeaderValue::from_static("c=3"))
+        );
+        assert!(entries.next().is_none());
+        drop(entries);
+
+        assert_eq!(headers.remove("Content-Type").unwrap(), "text/plain");
+        assert!(headers.remove("Content-Type").is_none());
+        assert!(!headers.contains_key("Content-Type"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "alloc"))]
+    fn test_too_many_headers() {
+        let mut headers = HeaderMap::new();
+        for _ in 0..MAX_RESPONSE_HEADERS {
+            headers
+                .append(
+                    HeaderName::from_static("X-Header"),
+                    HeaderValue::from_static("value"),
+                )
+                .unwrap();
+        }
+        assert!(headers
+            .append(
+                HeaderName::from_static("X-Header"),
+                HeaderValue::from_static("value")
+            )
+            .is_err());
+        assert!(headers
+            .insert(
+                HeaderName::from_static("X-Other"),
+                HeaderValue::from_static("value")
+            )
+            .is_err());
+
+        // Replacing frees the space of the previous values.
+        headers
+            .insert(
+                HeaderName::from_static("X-Header"),
+                HeaderValue::from_static("value"),
+            )
+            .unwrap();
+        assert_eq!(headers.len(), 1);
+    }
+
+    #[test]
+    fn test_header_name() {
+        assert!(HeaderName::try_from("X-Custom-Header").is_ok());
+        assert!(HeaderName::try_from("X-Injected\r\nSet-Cookie").is_err());
+        assert!(HeaderName::try_from("Content Type").is_err());
+        assert!(HeaderName::try_from("Content-Type:").is_err());
+        assert!(HeaderName::try_from("").is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid header name")]
+    fn test_header_name_from_static() {
+        HeaderName::from_static("a\r\nb");
+    }
+
+    #[test]
+    fn test_header_value() {
+        assert!(HeaderValue::try_from("text/plain; charset=utf-8").is_ok());
+        assert!(HeaderValue::try_from("a\r\nInjected: header").is_err());
+        assert_eq!(HeaderValue::from_display(404).unwrap(), "404");
+        assert!(HeaderValue::from_display("a\nb").is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid header value")]
+    fn test_header_value_from_static() {
+        HeaderValue::from_static("a\r\nb");
+    }
+}
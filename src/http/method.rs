@@ -1,3 +1,6 @@
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 use core::fmt;
 
 use self::Inner::*;
@@ -42,6 +45,55 @@ impl<'a> Method<'a> {
         }
     }
 
+    /// Converts a static str to a HTTP method, e.g. for extension methods like `PROPFIND`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the method is empty or not a valid token.
+    /// When used in a const context, this is a compile time error.
+    pub const fn from_static(src: &'static str) -> Method<'static> {
+        const STANDARD: [(&str, Method<'static>); 9] = [
+            ("GET", Method::GET),
+            ("PUT", Method::PUT),
+            ("POST", Method::POST),
+            ("HEAD", Method::HEAD),
+            ("PATCH", Method::PATCH),
+            ("TRACE", Method::TRACE),
+            ("DELETE", Method::DELETE),
+            ("OPTIONS", Method::OPTIONS),
+            ("CONNECT", Method::CONNECT),
+        ];
+
+        let bytes = src.as_bytes();
+        if bytes.is_empty() {
+            panic!("invalid HTTP method");
+        }
+        let mut i = 0;
+        while i < bytes.len() {
+            if !is_tchar(bytes[i]) {
+                panic!("invalid HTTP method");
+            }
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < STANDARD.len() {
+            let (name, method) = STANDARD[i];
+            if name.len() == bytes.len() {
+                let mut j = 0;
+                while j < bytes.len() && name.as_bytes()[j] == bytes[j] {
+                    j += 1;
+                }
+                if j == bytes.len() {
+                    return method;
+                }
+            }
+            i += 1;
+        }
+
+        Method(Extension(src))
+    }
+
     pub fn as_str(&self) -> &str {
         match self.0 {
             Options => "OPTIONS",
@@ -58,6 +110,14 @@ impl<'a> Method<'a> {
     }
 }
 
+/// Token characters, see [RFC 9110, Section 5.6.2](https://www.rfc-editor.org/rfc/rfc9110#section-5.6.2).
+pub(super) const fn is_tchar(b: u8) -> bool {
+    matches!(b,
+        b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`'
+        | b'|' | b'~' | b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z'
+    )
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 enum Inner<'a> {
     Options,
@@ -86,7 +146,7 @@ impl<'a, 'b> PartialEq<&'a Method<'b>> for Method<'b> {
     }
 }
 
-impl<'a, 'b> PartialEq<Method<'b>> for &'a Method<'b> {
+impl<'b> PartialEq<Method<'b>> for &Method<'b> {
     #[inline]
     fn eq(&self, other: &Method<'b>) -> bool {
         *self == other
@@ -156,6 +216,139 @@ impl<'a> TryFrom<&'a str> for Method<'a> {
     }
 }
 
+/// Maximum amount of extension methods a [`MethodSet`] can hold without the `alloc` feature.
+pub const MAX_EXTENSION_METHODS: usize = 4;
+
+#[cfg(not(feature = "alloc"))]
+type Extensions = heapless::Vec<&'static str, MAX_EXTENSION_METHODS>;
+#[cfg(feature = "alloc")]
+type Extensions = alloc::vec::Vec<&'static str>;
+
+/// A small set of methods, e.g. the methods allowed on a path.
+///
+/// Without the `alloc` feature, the set holds up to [`MAX_EXTENSION_METHODS`] extension
+/// methods. The [`Display`](fmt::Display) implementation formats the set as the value
+/// of an `Allow` header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MethodSet {
+    standard: u16,
+    extensions: Extensions,
+}
+
+impl MethodSet {
+    /// Standard methods in the order they are displayed.
+    const STANDARD: [Method<'static>; 9] = [
+        Method::GET,
+        Method::HEAD,
+        Method::POST,
+        Method::PUT,
+        Method::DELETE,
+        Method::CONNECT,
+        Method::OPTIONS,
+        Method::TRACE,
+        Method::PATCH,
+    ];
+
+    /// Creates an empty set.
+    pub const fn new() -> Self {
+        Self {
+            standard: 0,
+            extensions: Extensions::new(),
+        }
+    }
+
+    /// Returns `true` if the set contains no methods.
+    pub fn is_empty(&self) -> bool {
+        self.standard == 0 && self.extensions.is_empty()
+    }
+
+    /// Returns `true` if the set contains the `method`.
+    pub fn contains(&self, method: Method<'_>) -> bool {
+        match method.0 {
+            Extension(ext) => self.extensions.contains(&ext),
+            _ => self.standard & Self::bit(method) != 0,
+        }
+    }
+
+    /// Inserts the `method`.
+    ///
+    /// # Panics
+    ///
+    /// Without the `alloc` feature, panics if the set already contains
+    /// [`MAX_EXTENSION_METHODS`] other extension methods.
+    pub fn insert(&mut self, method: Method<'static>) {
+        match method.0 {
+            Extension(ext) => {
+                if !self.extensions.contains(&ext) {
+                    self.push_extension(ext);
+                }
+            }
+            _ => self.standard |= Self::bit(method),
+        }
+    }
+
+    /// Inserts all methods of `other`.
+    pub fn extend(&mut self, other: &MethodSet) {
+        self.standard |= other.standard;
+        for ext in &other.extensions {
+            self.insert(Method(Extension(ext)));
+        }
+    }
+
+    /// Returns the methods contained in both sets.
+    pub fn intersection(&self, other: &MethodSet) -> MethodSet {
+        let mut methods = MethodSet {
+            standard: self.standard & other.standard,
+            extensions: Extensions::new(),
+        };
+        for ext in &self.extensions {
+            if other.extensions.contains(ext) {
+                methods.push_extension(ext);
+            }
+        }
+        methods
+    }
+
+    /// Iterates over the methods, standard methods first.
+    pub fn iter(&self) -> impl Iterator<Item = Method<'static>> + '_ {
+        Self::STANDARD
+            .into_iter()
+            .filter(|&method| self.contains(method))
+            .chain(self.extensions.iter().map(|ext| Method(Extension(ext))))
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn push_extension(&mut self, ext: &'static str) {
+        if self.extensions.push(ext).is_err() {
+            panic!("a method set holds at most {MAX_EXTENSION_METHODS} extension methods without the `alloc` feature");
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn push_extension(&mut self, ext: &'static str) {
+        self.extensions.push(ext);
+    }
+
+    fn bit(method: Method<'_>) -> u16 {
+        Self::STANDARD
+            .iter()
+            .position(|standard| *standard == method)
+            .map_or(0, |i| 1 << i)
+    }
+}
+
+impl fmt::Display for MethodSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, method) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(method.as_str())?;
+        }
+        Ok(())
+    }
+}
+
 /// A possible error value when converting `Method` from bytes.
 pub struct InvalidMethod(());
 
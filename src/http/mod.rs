@@ -1,4 +1,5 @@
+mod header;
 mod method;
 mod status;
 
-pub use self::{method::*, status::*};
+pub use self::{header::*, method::*, status::*};
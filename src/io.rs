@@ -2,6 +2,8 @@ use core::convert::Infallible;
 
 pub use embedded_io_async::{ErrorType, Read, Write};
 
+use crate::response::{Body, SizeHint};
+
 pub struct Cursor<T> {
     inner: T,
     pos: usize,
@@ -37,3 +39,12 @@ where
         Ok(n)
     }
 }
+
+impl<T> Body for Cursor<T>
+where
+    T: AsRef<[u8]>,
+{
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.remaining_slice().len())
+    }
+}
@@ -1,8 +1,8 @@
 #![no_std]
 #![allow(stable_features)]
 #![feature(
+    associated_type_defaults,
     async_fn_in_trait,
-    type_alias_impl_trait,
     return_position_impl_trait_in_trait,
     maybe_uninit_slice,
     const_waker
@@ -10,17 +10,24 @@
 
 #[cfg(feature = "alloc")]
 pub mod alloc;
+mod chunked;
+mod config;
+mod connection;
+mod dispatch;
 pub(crate) mod either;
 mod error;
 pub mod extract;
+pub mod guard;
 mod handler;
 #[cfg(feature = "heapless")]
 pub mod heapless;
+mod host;
 pub mod http;
 mod io;
 pub(crate) mod macros;
 mod parse;
 mod path;
+mod percent;
 pub mod request;
 pub mod response;
 mod route;
@@ -28,11 +35,23 @@ mod router;
 mod service;
 mod utils;
 
+pub use config::{Buffers, ServerConfig, TrailingSlash};
+pub use dispatch::MAX_FIRST_SEGMENTS;
+pub use error::{InvalidUrl, ProtocolError};
 pub use extract::{FromRef, FromRequest, FromRequestParts};
+pub use host::HostPattern;
 pub use io::{ErrorType, Read, Write};
-pub use path::{PathSegments, Segment};
+#[doc(hidden)]
+pub use low_profile_macros::path as __path;
+pub use path::{
+    CaptureType, Captures, Named, NamedCaptures, PathSegments, Pattern, Raw, RawSegment, Rest,
+    Segment, SegmentPattern, StrSegment, Template, TemplateSegment, MAX_PATTERN_SEGMENTS,
+};
+pub use percent::{percent_decode, InvalidEncoding, MAX_DECODED_SEGMENT_LEN};
 pub use request::{Headers, Parts, Request};
 pub use response::{IntoResponse, Response};
-pub use route::{connect, delete, get, head, options, patch, post, put, trace};
+pub use route::{
+    connect, delete, get, head, on, options, patch, post, put, trace, Decision, MethodRouter, Route,
+};
 pub use router::Router;
-pub use service::Service;
+pub use service::{Service, ServiceError};
@@ -1,3 +1,4 @@
 mod path;
 
+pub(crate) use path::merge_slashes;
 pub use path::PathAndQuery;
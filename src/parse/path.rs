@@ -3,6 +3,7 @@ use crate::error::InvalidUrl;
 const NONE: u16 = u16::MAX;
 
 pub struct PathAndQuery<'a> {
+    authority: Option<&'a str>,
     data: &'a str,
     query: u16,
 }
@@ -16,6 +17,34 @@ impl<'a> PathAndQuery<'a> {
             return Err(InvalidUrl::TooLong);
         }
 
+        // The asterisk-form, used by `OPTIONS *` to address the server as a whole,
+        // it is rejected for other methods by the router.
+        if data == "*" {
+            return Ok(Self {
+                authority: None,
+                data,
+                query: NONE,
+            });
+        }
+
+        // The absolute-form, like `http://device.local/path`, has to be accepted by servers,
+        // its authority takes precedence over the `Host` header.
+        let (authority, data) = match absolute_form_len(data.as_bytes()) {
+            Some(len) => {
+                let (authority, data) = data.split_at(len);
+                let authority = &authority[authority.find("://").unwrap() + 3..];
+                if !authority.bytes().all(is_url_char) {
+                    return Err(InvalidUrl::InvalidUrlCodePoint);
+                }
+                // User information is not part of the host.
+                let host = authority
+                    .rsplit_once('@')
+                    .map_or(authority, |(_, host)| host);
+                (Some(host), data)
+            }
+            None => (None, data),
+        };
+
         let mut iter = data.as_bytes().iter().enumerate();
 
         for (i, c) in &mut iter {
@@ -28,17 +57,7 @@ impl<'a> PathAndQuery<'a> {
                     fragment = Some(i as u16);
                     break;
                 }
-                0x21
-                | 0x24..=0x3B
-                | 0x3D
-                | 0x40..=0x5F
-                | 0x61..=0x7A
-                | 0x7C
-                | 0x7E
-                // Not allowed by spec, but used in the wild
-                | b'"'
-                | b'{'
-                | b'}' => {}
+                &c if is_url_char(c) => {}
                 _ => return Err(InvalidUrl::InvalidUrlCodePoint),
             }
         }
@@ -59,11 +78,17 @@ impl<'a> PathAndQuery<'a> {
         let data = &data[..fragment.map_or(data.len(), |f| f as usize)];
 
         Ok(Self {
+            authority,
             data,
             query: query.unwrap_or(NONE),
         })
     }
 
+    /// The host and port of an absolute-form target, without user information.
+    pub fn authority(&self) -> Option<&'a str> {
+        self.authority
+    }
+
     pub fn path(&self) -> &'a str {
         let ret = if self.query == NONE {
             self.data
@@ -86,3 +111,63 @@ impl<'a> PathAndQuery<'a> {
         }
     }
 }
+
+fn is_url_char(c: u8) -> bool {
+    matches!(
+        c,
+        0x21
+        | 0x24..=0x3B
+        | 0x3D
+        | 0x40..=0x5F
+        | 0x61..=0x7A
+        | 0x7C
+        | 0x7E
+        // Not allowed by spec, but used in the wild
+        | b'"'
+        | b'{'
+        | b'}'
+    )
+}
+
+/// Returns the length of the `scheme://authority` of an absolute-form target.
+fn absolute_form_len(target: &[u8]) -> Option<usize> {
+    let scheme = target
+        .iter()
+        .position(|&b| !(b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.')))?;
+    if scheme == 0 || !target[0].is_ascii_alphabetic() || !target[scheme..].starts_with(b"://") {
+        return None;
+    }
+
+    let start = scheme + 3;
+    let len = target[start..]
+        .iter()
+        .position(|b| matches!(b, b'/' | b'?' | b'#'))
+        .map_or(target.len(), |len| start + len);
+    Some(len)
+}
+
+/// Collapses repeated slashes in the path of a request target in place, the query
+/// is moved to the end of the collapsed path. Returns the new length of the target.
+///
+/// Only the path of an absolute-form target is collapsed, other targets which do not
+/// start with a `/`, like the asterisk-form, are not changed.
+pub(crate) fn merge_slashes(target: &mut [u8]) -> usize {
+    let start = absolute_form_len(target).unwrap_or(0);
+    if target.get(start) != Some(&b'/') {
+        return target.len();
+    }
+
+    let mut len = start;
+    let mut in_path = true;
+    for i in start..target.len() {
+        let b = target[i];
+        in_path &= !matches!(b, b'?' | b'#');
+        if in_path && b == b'/' && len > start && target[len - 1] == b'/' {
+            continue;
+        }
+        target[len] = b;
+        len += 1;
+    }
+
+    len
+}
@@ -1,16 +1,219 @@
-use core::str::FromStr;
+use core::{fmt, marker::PhantomData, str::FromStr};
 
+use crate::percent::{self, InvalidEncoding, MAX_DECODED_SEGMENT_LEN};
+
+/// Matches a request path and extracts values from it.
+///
+/// Segments are percent-decoded before they are compared or parsed, except by
+/// [`Raw`], [`StrSegment`] and [`Rest`], which borrow the segments from the path as is.
 pub trait PathSegments {
-    type Output;
+    /// The values extracted from the path, they may borrow from the path.
+    type Output<'a>;
+
+    /// Whether the segment captures the rest of the path, including slashes,
+    /// when used in a tuple. Only the last element of a tuple may capture the rest.
+    const REST: bool = false;
+
+    fn parse<'a>(&self, path: &'a str) -> Option<Self::Output<'a>>;
+
+    /// Like [`PathSegments::parse`], but distinguishes a path which does not match
+    /// from a path which matches, but contains a segment which cannot be decoded.
+    ///
+    /// Routes reject the request with the [`InvalidEncoding`], instead of trying the next route.
+    fn try_parse<'a>(&self, path: &'a str) -> Result<Option<Self::Output<'a>>, InvalidEncoding> {
+        Ok(self.parse(path))
+    }
 
-    fn parse(&self, path: &str) -> Option<Self::Output>;
+    /// The decoded first segment of the paths which may match, routes are not tried
+    /// for requests with a different first segment.
+    ///
+    /// Defaults to `None` for paths starting with a capture, which is always correct.
+    fn first_segment(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Appends the segments of the matched paths to the `pattern`, used to detect
+    /// conflicting routes.
+    ///
+    /// Returns `false` if the path cannot be described, then it is never considered
+    /// to conflict with another path.
+    fn pattern(&self, pattern: &mut Pattern) -> bool {
+        let _ = pattern;
+        false
+    }
+}
+
+/// Maximum amount of segments a [`Pattern`] can describe.
+pub const MAX_PATTERN_SEGMENTS: usize = 16;
+
+/// A segment of a [`Pattern`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentPattern {
+    /// Matches the decoded literal.
+    Literal(&'static str),
+    /// Captures a single segment of the type.
+    Capture(CaptureType),
+    /// Captures the rest of the path.
+    Rest,
+}
+
+/// The type of a [`SegmentPattern::Capture`].
+///
+/// Captures of different types are assumed to match different segments, like
+/// `/users/{u32}` and `/users/{&str}` do not conflict.
+#[derive(Clone, Copy)]
+pub struct CaptureType {
+    name: &'static str,
+    parses: fn(&str) -> bool,
+}
+
+impl CaptureType {
+    /// A capture named `name`, which matches the decoded segments accepted by `parses`.
+    pub const fn new(name: &'static str, parses: fn(&str) -> bool) -> Self {
+        Self { name, parses }
+    }
+
+    /// A capture which parses the decoded segment into `T`.
+    pub fn of<T: FromStr>() -> Self {
+        Self::new(core::any::type_name::<T>(), |segment| {
+            segment.parse::<T>().is_ok()
+        })
+    }
+
+    /// A capture which matches every segment as a `&str`.
+    pub const fn str() -> Self {
+        Self::new("&str", |_| true)
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether the capture matches the decoded `segment`.
+    pub fn parses(&self, segment: &str) -> bool {
+        (self.parses)(segment)
+    }
+}
+
+impl PartialEq for CaptureType {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for CaptureType {}
+
+impl fmt::Debug for CaptureType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+/// The segments of the paths a route matches, see [`PathSegments::pattern`].
+///
+/// A route conflicts with an existing route with a common method, if its pattern
+/// [covers](Pattern::covers) the pattern of the existing route. Routes added later
+/// take precedence, the existing route would be unreachable. In debug builds the
+/// router panics when a conflicting route is added.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pattern {
+    segments: heapless::Vec<SegmentPattern, MAX_PATTERN_SEGMENTS>,
+}
+
+impl Pattern {
+    pub const fn new() -> Self {
+        Self {
+            segments: heapless::Vec::new(),
+        }
+    }
+
+    /// Appends a segment, returns `false` if the pattern is full.
+    pub fn push(&mut self, segment: SegmentPattern) -> bool {
+        self.segments.push(segment).is_ok()
+    }
+
+    /// Appends the literal segments of a path, the leading `/` is optional.
+    pub fn push_literal(&mut self, path: &'static str) -> bool {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        path.split('/')
+            .all(|segment| self.push(SegmentPattern::Literal(segment)))
+    }
+
+    pub fn segments(&self) -> &[SegmentPattern] {
+        &self.segments
+    }
+
+    /// Whether every path matching `other` also matches this pattern.
+    ///
+    /// A capture covers a literal it parses and captures of the same type, like
+    /// `/users/{&str}` covers `/users/me`, but `/users/{u32}` does not.
+    pub fn covers(&self, other: &Pattern) -> bool {
+        let (mut segments, mut others) = (self.segments().iter(), other.segments().iter());
+        loop {
+            match (segments.next(), others.next()) {
+                (None, None) => return true,
+                // The rest of the path starts with at least one segment.
+                (Some(SegmentPattern::Rest), Some(_)) => return true,
+                (Some(SegmentPattern::Literal(a)), Some(SegmentPattern::Literal(b))) if a == b => {}
+                (Some(SegmentPattern::Capture(ty)), Some(SegmentPattern::Literal(literal)))
+                    if ty.parses(literal) => {}
+                (Some(SegmentPattern::Capture(a)), Some(SegmentPattern::Capture(b))) if a == b => {}
+                _ => return false,
+            }
+        }
+    }
+
+    /// Appends the pattern of a nested route, the root of a nested router matches
+    /// the prefix itself.
+    pub(crate) fn join(&self, nested: &Pattern) -> Option<Pattern> {
+        let mut pattern = self.clone();
+        if self.segments.is_empty() || nested.segments() != [SegmentPattern::Literal("")] {
+            pattern.segments.extend_from_slice(nested.segments()).ok()?;
+        }
+        Some(pattern)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in self.segments() {
+            match segment {
+                SegmentPattern::Literal(literal) => write!(f, "/{literal}")?,
+                SegmentPattern::Capture(ty) => write!(f, "/{{{}}}", ty.name())?,
+                SegmentPattern::Rest => f.write_str("/{*}")?,
+            }
+        }
+        Ok(())
+    }
 }
 
 impl PathSegments for &'static str {
-    type Output = ();
+    type Output<'a> = ();
 
-    fn parse(&self, path: &str) -> Option<Self::Output> {
-        (self == &path).then_some(())
+    fn parse<'a>(&self, path: &'a str) -> Option<Self::Output<'a>> {
+        percent::eq_decoded(path, self).then_some(())
+    }
+
+    /// The first segment of a full path, or the literal itself as part of a tuple.
+    fn first_segment(&self) -> Option<&'static str> {
+        let path = self.strip_prefix('/').unwrap_or(self);
+        Some(path.split_once('/').map_or(path, |(segment, _)| segment))
+    }
+
+    fn pattern(&self, pattern: &mut Pattern) -> bool {
+        pattern.push_literal(self)
+    }
+}
+
+/// Splits off the next segment of a path, which does not contain the leading `/`.
+fn next_segment<'a>(rest: &mut Option<&'a str>) -> Option<&'a str> {
+    let path = rest.take()?;
+    match path.split_once('/') {
+        Some((segment, tail)) => {
+            *rest = Some(tail);
+            Some(segment)
+        }
+        None => Some(path),
     }
 }
 
@@ -20,22 +223,69 @@ macro_rules! impl_tuple_path_segments {
         where
             $($name: PathSegments),*
         {
-            type Output = ($($name::Output,)*);
+            type Output<'a> = ($($name::Output<'a>,)*);
+
+            fn parse<'a>(&self, path: &'a str) -> Option<Self::Output<'a>> {
+                self.try_parse(path).ok().flatten()
+            }
 
             #[allow(non_snake_case)]
-            fn parse(&self, path: &str) -> Option<Self::Output> {
-                let mut parts = path.strip_prefix('/')?.split('/');
+            fn try_parse<'a>(
+                &self,
+                path: &'a str,
+            ) -> Result<Option<Self::Output<'a>>, InvalidEncoding> {
+                const {
+                    let rest = [$($name::REST),*];
+                    let mut i = 0;
+                    while i + 1 < rest.len() {
+                        assert!(!rest[i], "only the last path segment may capture the rest");
+                        i += 1;
+                    }
+                };
+
+                let Some(path) = path.strip_prefix('/') else {
+                    return Ok(None);
+                };
+                let mut rest = Some(path);
 
+                // A segment which does not match takes precedence over an invalid encoding
+                // of a previous segment, the request may be matched by another route.
+                let mut invalid = None;
                 let ($($name,)*) = self;
                 $(
-                    let $name = $name.parse(parts.next()?)?;
+                    let segment = match $name::REST {
+                        true => rest.take(),
+                        false => next_segment(&mut rest),
+                    };
+                    let Some(segment) = segment else {
+                        return Ok(None);
+                    };
+                    let $name = match $name.try_parse(segment) {
+                        Ok(Some(value)) => Some(value),
+                        Ok(None) => return Ok(None),
+                        Err(err) => {
+                            invalid.get_or_insert(err);
+                            None
+                        }
+                    };
                 )*
 
-                match parts.next() {
-                    Some(_) => None,
-                    None => Some(($($name,)*)),
+                match (rest, invalid) {
+                    (Some(_), _) => Ok(None),
+                    (None, Some(err)) => Err(err),
+                    (None, None) => Ok((|| Some(($($name?,)*)))()),
                 }
             }
+
+            fn first_segment(&self) -> Option<&'static str> {
+                self.0.first_segment()
+            }
+
+            #[allow(non_snake_case)]
+            fn pattern(&self, pattern: &mut Pattern) -> bool {
+                let ($($name,)*) = self;
+                $($name.pattern(pattern))&&*
+            }
         }
     };
 }
@@ -63,15 +313,105 @@ impl<T> PathSegments for ParseSegment<T>
 where
     T: FromStr,
 {
-    type Output = T;
+    type Output<'a> = T;
+
+    fn parse<'a>(&self, path: &'a str) -> Option<Self::Output<'a>> {
+        self.try_parse(path).ok().flatten()
+    }
+
+    fn try_parse<'a>(&self, path: &'a str) -> Result<Option<Self::Output<'a>>, InvalidEncoding> {
+        let mut buf = [0; MAX_DECODED_SEGMENT_LEN];
+        let segment = percent::decode_segment(path, &mut buf)?;
+        Ok(segment.parse().ok())
+    }
+
+    fn pattern(&self, pattern: &mut Pattern) -> bool {
+        pattern.push(SegmentPattern::Capture(CaptureType::of::<T>()))
+    }
+}
+
+/// A segment parsed without percent-decoding it, it may contain an encoded `/`.
+///
+/// `Raw::<heapless::String<16>>::segment()` captures `a%2Fb` as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Raw<T>(pub T);
+
+pub struct RawSegment<T: FromStr>(PhantomData<T>);
+
+impl<T> PathSegments for RawSegment<T>
+where
+    T: FromStr,
+{
+    type Output<'a> = Raw<T>;
+
+    fn parse<'a>(&self, path: &'a str) -> Option<Self::Output<'a>> {
+        path.parse().ok().map(Raw)
+    }
+
+    fn pattern(&self, pattern: &mut Pattern) -> bool {
+        let ty = CaptureType::new(core::any::type_name::<Raw<T>>(), |segment| {
+            segment.parse::<T>().is_ok()
+        });
+        pattern.push(SegmentPattern::Capture(ty))
+    }
+}
+
+impl<T> Segment for Raw<T>
+where
+    T: FromStr,
+{
+    type P = RawSegment<T>;
+
+    fn segment() -> Self::P {
+        RawSegment(PhantomData)
+    }
+}
+
+/// Captures a single segment as a `&str` borrowed from the request path, without copying it.
+///
+/// The segment is not percent-decoded, see [`percent_decode`](crate::percent_decode).
+/// Handlers extract the captured segment as `Path<(&str,)>`, see [`Path`](crate::extract::Path).
+#[derive(Debug, Clone, Copy)]
+pub struct StrSegment;
+
+impl PathSegments for StrSegment {
+    type Output<'a> = &'a str;
+
+    fn parse<'a>(&self, path: &'a str) -> Option<Self::Output<'a>> {
+        Some(path)
+    }
+
+    fn pattern(&self, pattern: &mut Pattern) -> bool {
+        pattern.push(SegmentPattern::Capture(CaptureType::str()))
+    }
+}
+
+/// Captures the rest of the path, including slashes, as the last element of a tuple.
+///
+/// For example `("static", Rest)` matches `/static/css/main.css` and captures `css/main.css`.
+/// A trailing slash, like in `/static/`, is captured as an empty string, but `/static`
+/// does not match. The captured path is not percent-decoded.
+///
+/// Handlers extract the captured path as `Path<((), &str)>`, see [`Path`](crate::extract::Path).
+#[derive(Debug, Clone, Copy)]
+pub struct Rest;
+
+impl PathSegments for Rest {
+    type Output<'a> = &'a str;
+
+    const REST: bool = true;
+
+    fn parse<'a>(&self, path: &'a str) -> Option<Self::Output<'a>> {
+        Some(path)
+    }
 
-    fn parse(&self, path: &str) -> Option<Self::Output> {
-        path.parse().ok()
+    fn pattern(&self, pattern: &mut Pattern) -> bool {
+        pattern.push(SegmentPattern::Rest)
     }
 }
 
 pub trait Segment {
-    type P: PathSegments<Output = Self>;
+    type P: for<'a> PathSegments<Output<'a> = Self>;
 
     fn segment() -> Self::P;
 }
@@ -86,3 +426,693 @@ where
         ParseSegment(Default::default())
     }
 }
+
+/// A path parsed from a template like `/users/{id: u32}/posts/{post: u32}`, see [`path!`](crate::path!).
+///
+/// Literal segments must match exactly, `{name}` captures a single segment and
+/// `{*name}` captures the rest of the path as the last segment. The captured
+/// segments are parsed into the tuple `T` in the order they appear in the template,
+/// or captured by name with [`Named`].
+pub struct Template<T> {
+    segments: &'static [TemplateSegment],
+    _captures: PhantomData<fn() -> T>,
+}
+
+/// A segment of a [`Template`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateSegment {
+    /// A literal segment, compared with the percent-decoded path segment.
+    Literal(&'static str),
+    /// A `{name}` capture of a single segment.
+    Capture(&'static str),
+    /// A `{*name}` capture of the rest of the path.
+    Rest(&'static str),
+}
+
+impl TemplateSegment {
+    const fn capture_name(&self) -> Option<&'static str> {
+        match *self {
+            Self::Literal(_) => None,
+            Self::Capture(name) | Self::Rest(name) => Some(name),
+        }
+    }
+}
+
+impl<T: Captures> Template<T> {
+    /// Creates a template from its segments, the captures are parsed into `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the template is malformed or its captures do not match `T`.
+    /// When used in a const context, this is a compile time error.
+    pub const fn new(segments: &'static [TemplateSegment]) -> Self {
+        let captures = validate(segments);
+        assert!(
+            captures == T::LEN,
+            "path template captures do not match their types"
+        );
+
+        Self {
+            segments,
+            _captures: PhantomData,
+        }
+    }
+}
+
+impl Template<Named> {
+    /// Creates a template from its segments which captures segments by name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the template is malformed.
+    /// When used in a const context, this is a compile time error.
+    pub const fn named(segments: &'static [TemplateSegment]) -> Self {
+        validate(segments);
+
+        Self {
+            segments,
+            _captures: PhantomData,
+        }
+    }
+}
+
+impl<T> Template<T> {
+    /// The segments the template was parsed into.
+    pub fn segments(&self) -> &'static [TemplateSegment] {
+        self.segments
+    }
+
+    /// Matches the literal segments of the template against the `path`.
+    fn captures<'a>(&self, path: &'a str) -> Option<NamedCaptures<'a>> {
+        let captures = NamedCaptures {
+            segments: self.segments,
+            path: path.strip_prefix('/')?,
+        };
+
+        let mut rest = Some(captures.path);
+        for segment in self.segments {
+            let value = next_value(segment, &mut rest)?;
+            match segment {
+                TemplateSegment::Literal(literal) if !percent::eq_decoded(value, literal) => {
+                    return None
+                }
+                _ => {}
+            }
+        }
+
+        rest.is_none().then_some(captures)
+    }
+
+    /// The first segment of the template, unless it is a capture.
+    fn first_literal(&self) -> Option<&'static str> {
+        match self.segments[0] {
+            TemplateSegment::Literal(literal) => Some(literal),
+            _ => None,
+        }
+    }
+
+    /// The segments of the template, `capture` appends the pattern of the capture
+    /// at an index, regardless of its name.
+    fn segment_patterns(
+        &self,
+        pattern: &mut Pattern,
+        capture: impl Fn(usize, &mut Pattern) -> bool,
+    ) -> bool {
+        let mut captures = 0;
+        self.segments.iter().all(|segment| match *segment {
+            TemplateSegment::Literal(literal) => pattern.push(SegmentPattern::Literal(literal)),
+            TemplateSegment::Capture(_) => {
+                captures += 1;
+                capture(captures - 1, pattern)
+            }
+            TemplateSegment::Rest(_) => pattern.push(SegmentPattern::Rest),
+        })
+    }
+}
+
+/// Validates the segments of a template and returns the amount of its captures.
+const fn validate(segments: &[TemplateSegment]) -> usize {
+    assert!(!segments.is_empty(), "path template must not be empty");
+
+    let mut captures = 0;
+    let mut i = 0;
+    while i < segments.len() {
+        let last = i + 1 == segments.len();
+
+        if let Some(name) = segments[i].capture_name() {
+            assert!(
+                !matches!(segments[i], TemplateSegment::Rest(_)) || last,
+                "a rest capture must be the last segment of a path template"
+            );
+            assert!(
+                is_ident(name.as_bytes()),
+                "capture names must be identifiers"
+            );
+            let mut prev = 0;
+            while prev < i {
+                if let Some(other) = segments[prev].capture_name() {
+                    assert!(
+                        !eq(name.as_bytes(), other.as_bytes()),
+                        "duplicate capture in path template"
+                    );
+                }
+                prev += 1;
+            }
+            captures += 1;
+        } else if let TemplateSegment::Literal(literal) = segments[i] {
+            let literal = literal.as_bytes();
+            assert!(
+                !literal.is_empty() || last,
+                "empty segment in path template"
+            );
+            let mut j = 0;
+            while j < literal.len() {
+                // Literals are compared with the decoded path.
+                assert!(
+                    literal[j] != b'%',
+                    "percent-encoding is not supported in path template literals"
+                );
+                assert!(is_pchar(literal[j]), "invalid character in path template");
+                j += 1;
+            }
+        }
+
+        i += 1;
+    }
+
+    captures
+}
+
+/// Returns the part of the `path` which is matched against the template `segment`.
+fn next_value<'a>(segment: &TemplateSegment, path: &mut Option<&'a str>) -> Option<&'a str> {
+    match segment {
+        TemplateSegment::Rest(_) => path.take(),
+        _ => next_segment(path),
+    }
+}
+
+impl<T: Captures> PathSegments for Template<T> {
+    type Output<'a> = T;
+
+    fn parse<'a>(&self, path: &'a str) -> Option<Self::Output<'a>> {
+        self.try_parse(path).ok().flatten()
+    }
+
+    fn try_parse<'a>(&self, path: &'a str) -> Result<Option<Self::Output<'a>>, InvalidEncoding> {
+        // All literals are compared before any capture is parsed.
+        match self.captures(path) {
+            Some(captures) => T::parse(captures.iter().map(|(_, value)| value)),
+            None => Ok(None),
+        }
+    }
+
+    fn first_segment(&self) -> Option<&'static str> {
+        self.first_literal()
+    }
+
+    fn pattern(&self, pattern: &mut Pattern) -> bool {
+        self.segment_patterns(pattern, T::capture_pattern)
+    }
+}
+
+impl PathSegments for Template<Named> {
+    type Output<'a> = NamedCaptures<'a>;
+
+    fn parse<'a>(&self, path: &'a str) -> Option<Self::Output<'a>> {
+        self.captures(path)
+    }
+
+    fn first_segment(&self) -> Option<&'static str> {
+        self.first_literal()
+    }
+
+    fn pattern(&self, pattern: &mut Pattern) -> bool {
+        self.segment_patterns(pattern, |_, pattern| {
+            pattern.push(SegmentPattern::Capture(CaptureType::str()))
+        })
+    }
+}
+
+/// Marker for a [`Template`] which captures segments by name, see [`NamedCaptures`].
+#[derive(Debug, Clone, Copy)]
+pub struct Named;
+
+/// The segments captured by a [`Template<Named>`], borrowed from the request path.
+///
+/// The captured segments are not percent-decoded, see [`percent_decode`](crate::percent_decode).
+/// With the `serde` feature, the captures can be deserialized into a struct or tuple
+/// by the [`Path`](crate::extract::Path) extractor.
+#[derive(Debug, Clone, Copy)]
+pub struct NamedCaptures<'a> {
+    segments: &'static [TemplateSegment],
+    path: &'a str,
+}
+
+impl<'a> NamedCaptures<'a> {
+    /// Iterates over the names and values of the captures in the order of the template.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&'static str, &'a str)> {
+        let mut rest = Some(self.path);
+        let iter = self.segments.iter().filter_map(move |segment| {
+            // The path was matched against the template.
+            let value = next_value(segment, &mut rest).unwrap_or_default();
+            Some((segment.capture_name()?, value))
+        });
+        ExactSize {
+            iter,
+            len: self.len(),
+        }
+    }
+
+    /// Returns the value of the capture with the `name`.
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.iter()
+            .find_map(|(n, value)| (n == name).then_some(value))
+    }
+
+    /// Returns the amount of captures.
+    pub fn len(&self) -> usize {
+        self.segments
+            .iter()
+            .filter(|segment| segment.capture_name().is_some())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+struct ExactSize<I> {
+    iter: I,
+    len: usize,
+}
+
+impl<I: Iterator> Iterator for ExactSize<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<I: Iterator> ExactSizeIterator for ExactSize<I> {}
+
+impl<T> Clone for Template<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Template<T> {}
+
+impl<T> fmt::Debug for Template<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Template")
+            .field(&format_args!("{self}"))
+            .finish()
+    }
+}
+
+impl<T> fmt::Display for Template<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in self.segments {
+            match segment {
+                TemplateSegment::Literal(literal) => write!(f, "/{literal}")?,
+                TemplateSegment::Capture(name) => write!(f, "/{{{name}}}")?,
+                TemplateSegment::Rest(name) => write!(f, "/{{*{name}}}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+const fn is_ident(name: &[u8]) -> bool {
+    if name.is_empty() || name[0].is_ascii_digit() {
+        return false;
+    }
+    let mut i = 0;
+    while i < name.len() {
+        if !(name[i].is_ascii_alphanumeric() || name[i] == b'_') {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Characters allowed in a path segment, see [RFC 3986, Section 3.3](https://www.rfc-editor.org/rfc/rfc3986#section-3.3).
+const fn is_pchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric()
+        || matches!(
+            b,
+            b'-' | b'.'
+                | b'_'
+                | b'~'
+                | b'!'
+                | b'$'
+                | b'&'
+                | b'\''
+                | b'('
+                | b')'
+                | b'*'
+                | b'+'
+                | b','
+                | b';'
+                | b'='
+                | b':'
+                | b'@'
+                | b'%'
+        )
+}
+
+const fn eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// The values captured by a [`Template`], a tuple of types implementing [`Segment`].
+pub trait Captures: Sized {
+    /// The amount of captured segments.
+    const LEN: usize;
+
+    fn parse<'a>(segments: impl Iterator<Item = &'a str>) -> Result<Option<Self>, InvalidEncoding>;
+
+    /// Appends the pattern of the capture at `index`, see [`PathSegments::pattern`].
+    fn capture_pattern(index: usize, pattern: &mut Pattern) -> bool;
+}
+
+macro_rules! impl_captures {
+    ($len:literal $(, $name:ident)*) => {
+        impl<$($name),*> Captures for ($($name,)*)
+        where
+            $($name: Segment),*
+        {
+            const LEN: usize = $len;
+
+            #[allow(unused_mut, unused_variables)]
+            fn parse<'a>(
+                mut segments: impl Iterator<Item = &'a str>,
+            ) -> Result<Option<Self>, InvalidEncoding> {
+                Ok(Some(($(
+                    match segments.next().map(|segment| $name::segment().try_parse(segment)) {
+                        Some(Ok(Some(value))) => value,
+                        Some(Err(err)) => return Err(err),
+                        _ => return Ok(None),
+                    },
+                )*)))
+            }
+
+            #[allow(unused_mut, unused_variables, unused_assignments)]
+            fn capture_pattern(index: usize, pattern: &mut Pattern) -> bool {
+                let mut i = 0;
+                $(
+                    if i == index {
+                        return $name::segment().pattern(pattern);
+                    }
+                    i += 1;
+                )*
+                false
+            }
+        }
+    };
+}
+
+impl_captures!(0);
+impl_captures!(1, T1);
+impl_captures!(2, T1, T2);
+impl_captures!(3, T1, T2, T3);
+impl_captures!(4, T1, T2, T3, T4);
+impl_captures!(5, T1, T2, T3, T4, T5);
+impl_captures!(6, T1, T2, T3, T4, T5, T6);
+impl_captures!(7, T1, T2, T3, T4, T5, T6, T7);
+impl_captures!(8, T1, T2, T3, T4, T5, T6, T7, T8);
+
+/// Creates a [`Template`] path from a template string.
+///
+/// Captures are written as `{name: Type}` for a single segment, or `{*name: Type}`
+/// for the rest of the path, and are parsed into a tuple of their types in the order
+/// they appear in the template. Without types the segments are captured by [`Named`].
+/// A malformed template is a compile time error.
+///
+/// ```
+/// use low_profile::{extract::Path, path, Router};
+///
+/// let router = Router::<(), _>::new().get(
+///     path!("/users/{id: u32}/posts/{post: u32}"),
+///     |Path((id, post)): Path<(u32, u32)>| async move {
+///         let _ = (id, post);
+///         "post"
+///     },
+/// );
+/// # let _ = router;
+/// ```
+#[macro_export]
+macro_rules! path {
+    ($template:literal $(,)?) => {
+        $crate::__path!($crate, $template)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_borrowed_segments() {
+        let path = ("users", StrSegment, "files", Rest);
+
+        assert_eq!(
+            path.parse("/users/alice/files/a/b.txt"),
+            Some(((), "alice", (), "a/b.txt"))
+        );
+        assert_eq!(
+            path.parse("/users/alice/files/"),
+            Some(((), "alice", (), ""))
+        );
+        assert_eq!(path.parse("/users/alice/files"), None);
+        assert_eq!(path.parse("/users/alice"), None);
+    }
+
+    #[test]
+    fn test_parse_segment() {
+        let path = ("users", u32::segment());
+
+        assert_eq!(path.parse("/users/7"), Some(((), 7)));
+        assert_eq!(path.parse("/users/x"), None);
+        assert_eq!(path.parse("/users/7/"), None);
+    }
+
+    #[test]
+    fn test_template() {
+        let path = crate::path!("/users/{id: u32}/posts/{post: u32}");
+
+        assert_eq!(path.parse("/users/1/posts/2"), Some((1, 2)));
+        assert_eq!(path.parse("/users/1/posts/x"), None);
+        assert_eq!(path.parse("/users/1/comments/2"), None);
+        assert_eq!(path.parse("/users/1/posts"), None);
+        assert_eq!(path.parse("/users/1/posts/2/"), None);
+
+        let path = Template::<()>::new(&[TemplateSegment::Literal("about")]);
+        assert_eq!(path.parse("/about"), Some(()));
+        assert_eq!(path.parse("/about/"), None);
+
+        let path = Template::<()>::new(&[TemplateSegment::Literal("")]);
+        assert_eq!(path.parse("/"), Some(()));
+        assert_eq!(path.parse("/about"), None);
+    }
+
+    #[test]
+    fn test_named_captures() {
+        let path = crate::path!("/users/{id}/files/{*file}");
+
+        let captures = path.parse("/users/alice/files/a/b.txt").unwrap();
+        assert_eq!(captures.len(), 2);
+        assert_eq!(captures.get("id"), Some("alice"));
+        assert_eq!(captures.get("file"), Some("a/b.txt"));
+        assert_eq!(captures.get("user"), None);
+        assert!(captures.iter().eq([("id", "alice"), ("file", "a/b.txt")]));
+
+        assert!(path.parse("/users/alice/posts/a").is_none());
+        assert!(crate::path!("/about").parse("/about").unwrap().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate capture in path template")]
+    fn test_template_duplicate_capture() {
+        Template::<Named>::named(&[
+            TemplateSegment::Literal("users"),
+            TemplateSegment::Capture("id"),
+            TemplateSegment::Literal("posts"),
+            TemplateSegment::Capture("id"),
+        ]);
+    }
+
+    #[test]
+    fn test_template_rest() {
+        let path = crate::path!("/files/{*file: heapless::String<16>}");
+
+        assert_eq!(path.parse("/files/a/b.txt").unwrap().0, "a/b.txt");
+        assert_eq!(path.parse("/files/").unwrap().0, "");
+        assert_eq!(path.parse("/files"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "path template captures do not match their types")]
+    fn test_template_type_mismatch() {
+        Template::<(u32, u32)>::new(&[
+            TemplateSegment::Literal("users"),
+            TemplateSegment::Capture("id"),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty segment in path template")]
+    fn test_template_empty_segment() {
+        Template::<()>::new(&[
+            TemplateSegment::Literal("users"),
+            TemplateSegment::Literal(""),
+            TemplateSegment::Literal("posts"),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "a rest capture must be the last segment of a path template")]
+    fn test_template_rest_not_last() {
+        Template::<Named>::named(&[
+            TemplateSegment::Rest("file"),
+            TemplateSegment::Literal("raw"),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "percent-encoding is not supported in path template literals")]
+    fn test_template_percent_literal() {
+        Template::<()>::new(&[TemplateSegment::Literal("my%20file")]);
+    }
+
+    #[test]
+    fn test_percent_decoding() {
+        type Name = heapless::String<16>;
+
+        assert_eq!(PathSegments::parse(&"/my file", "/my%20file"), Some(()));
+
+        let path = ("files", Name::segment());
+        assert_eq!(path.parse("/files/my%20file").unwrap().1, "my file");
+        assert_eq!(
+            path.try_parse("/files/a%2Fb").map(|path| path.is_some()),
+            Err(InvalidEncoding::EncodedSlash)
+        );
+        assert_eq!(
+            path.try_parse("/files/a%zz").map(|path| path.is_some()),
+            Err(InvalidEncoding::InvalidEscape)
+        );
+        // A later segment which does not match takes precedence.
+        let path = ("files", Name::segment(), "raw");
+        assert_eq!(path.try_parse("/files/a%2Fb/x"), Ok(None));
+
+        let path = ("files", Raw::<Name>::segment());
+        assert_eq!(path.parse("/files/a%2Fb").unwrap().1 .0, "a%2Fb");
+
+        let path = crate::path!("/files/{name: Name}");
+        assert_eq!(path.parse("/files/my%20file").unwrap().0, "my file");
+        assert_eq!(
+            path.try_parse("/files/a%2Fb").map(|path| path.is_some()),
+            Err(InvalidEncoding::EncodedSlash)
+        );
+        assert_eq!(path.try_parse("/fil%65s/a%2Fb/c"), Ok(None));
+    }
+
+    #[test]
+    fn test_first_segment() {
+        assert_eq!("/users".first_segment(), Some("users"));
+        assert_eq!("/users/1".first_segment(), Some("users"));
+        assert_eq!(("users", u32::segment()).first_segment(), Some("users"));
+        assert_eq!(
+            crate::path!("/users/{id: u32}").first_segment(),
+            Some("users")
+        );
+        assert_eq!(crate::path!("/{id}/users").first_segment(), None);
+        assert_eq!((u32::segment(), "users").first_segment(), None);
+        assert_eq!("/".first_segment(), Some(""));
+    }
+
+    #[test]
+    fn test_pattern() {
+        let pattern = |path: &dyn Fn(&mut Pattern) -> bool| {
+            let mut pattern = Pattern::new();
+            assert!(path(&mut pattern));
+            pattern
+        };
+
+        let users = pattern(&|p| ("users", u32::segment()).pattern(p));
+        assert_eq!(
+            users.segments(),
+            [
+                SegmentPattern::Literal("users"),
+                SegmentPattern::Capture(CaptureType::of::<u32>())
+            ]
+        );
+        assert_eq!(
+            users,
+            pattern(&|p| crate::path!("/users/{id: u32}").pattern(p))
+        );
+        assert_eq!(users.to_string(), "/users/{u32}");
+        let names = pattern(&|p| crate::path!("/users/{name}").pattern(p));
+        assert_eq!(names, pattern(&|p| ("users", StrSegment).pattern(p)));
+        assert_ne!(names, users);
+
+        let files = pattern(&|p| ("files", Rest).pattern(p));
+        assert_eq!(
+            files,
+            pattern(&|p| crate::path!("/files/{*path}").pattern(p))
+        );
+        assert_eq!(files.to_string(), "/files/{*}");
+
+        let about = pattern(&|p| "/about/team".pattern(p));
+        assert_eq!(about, pattern(&|p| ("about", "team").pattern(p)));
+        assert_ne!(about, pattern(&|p| "/about/team/".pattern(p)));
+        assert_eq!(pattern(&|p| "/".pattern(p)).to_string(), "/");
+
+        let api = pattern(&|p| "/api".pattern(p));
+        assert_eq!(api.join(&pattern(&|p| "/".pattern(p))), Some(api.clone()));
+        assert_eq!(api.join(&users).unwrap().to_string(), "/api/users/{u32}");
+
+        assert!(users.covers(&users));
+        assert!(users.covers(&pattern(&|p| "/users/1".pattern(p))));
+        assert!(!users.covers(&pattern(&|p| "/users/me".pattern(p))));
+        assert!(!users.covers(&pattern(&|p| "/users".pattern(p))));
+        assert!(!users.covers(&pattern(&|p| "/posts/1".pattern(p))));
+        assert!(!users.covers(&names));
+        assert!(!names.covers(&users));
+        assert!(names.covers(&pattern(&|p| "/users/me".pattern(p))));
+        assert!(!pattern(&|p| "/users/me".pattern(p)).covers(&names));
+        assert!(files.covers(&pattern(&|p| "/files/a/b".pattern(p))));
+        assert!(files.covers(&pattern(&|p| ("files", u32::segment(), Rest).pattern(p))));
+        assert!(files.covers(&pattern(&|p| "/files/".pattern(p))));
+        assert!(!files.covers(&pattern(&|p| "/files".pattern(p))));
+        assert!(!pattern(&|p| "/files/a/b".pattern(p)).covers(&files));
+        assert!(!about.covers(&pattern(&|p| "/about/team/".pattern(p))));
+
+        let mut long = Pattern::new();
+        assert!(!long.push_literal("/a/b/c/d/e/f/g/h/i/j/k/l/m/n/o/p/q"));
+    }
+}
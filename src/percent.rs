@@ -0,0 +1,189 @@
+//! Percent-decoding of path segments, see [RFC 3986, Section 2.1](https://www.rfc-editor.org/rfc/rfc3986#section-2.1).
+
+use core::fmt;
+
+use crate::{http::StatusCode, IntoResponse, Response};
+
+/// Maximum length of a percent-encoded path segment which is decoded before it is parsed.
+///
+/// Segments without a `%` are parsed as is and are not limited.
+pub const MAX_DECODED_SEGMENT_LEN: usize = 128;
+
+/// A path segment which cannot be percent-decoded.
+///
+/// Used as a rejection by routes, when the path matches except for the encoding of a segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidEncoding {
+    /// A `%` is not followed by two hex digits.
+    InvalidEscape,
+    /// The segment contains an encoded `/`, which is only allowed by [`Raw`](crate::Raw) segments.
+    EncodedSlash,
+    /// The decoded segment is not valid UTF-8.
+    InvalidUtf8,
+    /// The segment is longer than [`MAX_DECODED_SEGMENT_LEN`].
+    TooLong,
+}
+
+impl InvalidEncoding {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::TooLong => StatusCode::URI_TOO_LONG,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidEscape => "Invalid percent-encoding in path",
+            Self::EncodedSlash => "Encoded slash in path segment",
+            Self::InvalidUtf8 => "Invalid UTF-8 in path segment",
+            Self::TooLong => "Path segment too long",
+        }
+    }
+}
+
+impl fmt::Display for InvalidEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl IntoResponse for InvalidEncoding {
+    type Body = &'static [u8];
+
+    fn into_response(self) -> Response<Self::Body> {
+        (self.status(), self.as_str()).into_response()
+    }
+}
+
+/// Decodes the percent-encoded `input` into `buf`.
+///
+/// Unlike path segments matched by routes, an encoded `/` is decoded as well.
+/// This can be used to decode [`StrSegment`](crate::StrSegment) and [`Rest`](crate::Rest) captures.
+pub fn percent_decode<'b>(input: &str, buf: &'b mut [u8]) -> Result<&'b str, InvalidEncoding> {
+    decode_into(input, buf, true)
+}
+
+/// Decodes a single path segment, which is only copied into `buf` if it contains a `%`.
+pub(crate) fn decode_segment<'b>(
+    segment: &'b str,
+    buf: &'b mut [u8; MAX_DECODED_SEGMENT_LEN],
+) -> Result<&'b str, InvalidEncoding> {
+    match segment.contains('%') {
+        true => decode_into(segment, buf, false),
+        false => Ok(segment),
+    }
+}
+
+/// Compares a percent-encoded `path` with a decoded `literal`.
+///
+/// An encoded `/` never matches, invalid encodings do not match anything.
+pub(crate) fn eq_decoded(path: &str, literal: &str) -> bool {
+    let mut literal = literal.bytes();
+    for byte in Decode::new(path) {
+        match (byte, literal.next()) {
+            (Ok((b'/', true)), _) | (Err(_), _) => return false,
+            (Ok((byte, _)), Some(expected)) if byte == expected => {}
+            _ => return false,
+        }
+    }
+    literal.next().is_none()
+}
+
+fn decode_into<'b>(
+    input: &str,
+    buf: &'b mut [u8],
+    allow_slash: bool,
+) -> Result<&'b str, InvalidEncoding> {
+    let mut len = 0;
+    for byte in Decode::new(input) {
+        let byte = match byte? {
+            (b'/', true) if !allow_slash => return Err(InvalidEncoding::EncodedSlash),
+            (byte, _) => byte,
+        };
+        *buf.get_mut(len).ok_or(InvalidEncoding::TooLong)? = byte;
+        len += 1;
+    }
+
+    core::str::from_utf8(&buf[..len]).map_err(|_| InvalidEncoding::InvalidUtf8)
+}
+
+/// Iterates over the decoded bytes, with a flag whether the byte was percent-encoded.
+struct Decode<'a> {
+    bytes: core::slice::Iter<'a, u8>,
+}
+
+impl<'a> Decode<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            bytes: input.as_bytes().iter(),
+        }
+    }
+}
+
+impl Iterator for Decode<'_> {
+    type Item = Result<(u8, bool), InvalidEncoding>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = *self.bytes.next()?;
+        if byte != b'%' {
+            return Some(Ok((byte, false)));
+        }
+
+        let mut hex = || {
+            self.bytes
+                .next()
+                .and_then(|&b| (b as char).to_digit(16))
+                .ok_or(InvalidEncoding::InvalidEscape)
+        };
+        Some(hex().and_then(|high| Ok(((high << 4 | hex()?) as u8, true))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_segment() {
+        let mut buf = [0; MAX_DECODED_SEGMENT_LEN];
+        assert_eq!(decode_segment("my%20file", &mut buf), Ok("my file"));
+        assert_eq!(decode_segment("caf%C3%A9", &mut buf), Ok("café"));
+        assert_eq!(decode_segment("plain", &mut buf), Ok("plain"));
+        assert_eq!(
+            decode_segment("a%2Fb", &mut buf),
+            Err(InvalidEncoding::EncodedSlash)
+        );
+        assert_eq!(
+            decode_segment("a%2", &mut buf),
+            Err(InvalidEncoding::InvalidEscape)
+        );
+        assert_eq!(
+            decode_segment("a%zz", &mut buf),
+            Err(InvalidEncoding::InvalidEscape)
+        );
+        assert_eq!(
+            decode_segment("%FF", &mut buf),
+            Err(InvalidEncoding::InvalidUtf8)
+        );
+
+        let mut buf = [0; 4];
+        assert_eq!(percent_decode("a%2Fb", &mut buf), Ok("a/b"));
+        assert_eq!(
+            percent_decode("a%20b%20c", &mut buf),
+            Err(InvalidEncoding::TooLong)
+        );
+    }
+
+    #[test]
+    fn test_eq_decoded() {
+        assert!(eq_decoded("/my%20file", "/my file"));
+        assert!(!eq_decoded("/my%20file", "/my%20file"));
+        assert!(eq_decoded("/a/b", "/a/b"));
+        assert!(!eq_decoded("/a%2Fb", "/a/b"));
+        assert!(!eq_decoded("/a%zz", "/a%zz"));
+        assert!(!eq_decoded("/ab", "/a"));
+        assert!(!eq_decoded("/a", "/ab"));
+    }
+}
@@ -1,6 +1,12 @@
 use core::{fmt, mem::MaybeUninit, str::Utf8Error};
 
-use crate::{http::Method, ErrorType, Read};
+use crate::{
+    chunked::{ChunkedDecoder, InvalidChunk},
+    dispatch::Candidates,
+    error::ProtocolError,
+    http::{Method, MethodSet},
+    ErrorType, Read,
+};
 
 pub struct Request<'a, R, P> {
     pub(crate) parts: Parts<'a, P>,
@@ -24,6 +30,11 @@ impl<'a, R, P> Request<'a, R, P> {
         self.parts.path
     }
 
+    /// The values extracted from the path by the matched route, see [`PathSegments`](crate::PathSegments).
+    pub fn extracted_path(&self) -> &P {
+        &self.parts.extracted_path
+    }
+
     pub fn body(&self) -> &Body<'a, R> {
         &self.body
     }
@@ -55,17 +66,52 @@ pub struct Parts<'a, P> {
     pub path: &'a str,
     pub query: Option<&'a str>,
     pub headers: Headers<'a>,
+    /// The authority of an absolute-form request target.
+    pub(crate) authority: Option<&'a str>,
+    /// The method matched by routes, `GET` for a `HEAD` request answered by a `GET` handler.
+    pub(crate) route_method: Method<'a>,
     pub(crate) extracted_path: P,
+    /// The part of the path matched by routes, without the prefix of nested routers.
+    pub(crate) route_path: &'a str,
+    /// The routes of the current chain which may match the `route_path`.
+    pub(crate) route_candidates: Candidates,
+    /// Methods of the routes which matched the path, but not the method.
+    pub(crate) allowed_methods: MethodSet,
 }
 
 impl<'a, P> Parts<'a, P> {
+    /// The values extracted from the path by the matched route, see [`PathSegments`](crate::PathSegments).
+    pub fn extracted_path(&self) -> &P {
+        &self.extracted_path
+    }
+
+    /// The host the request is addressed to, including the port if there is one.
+    ///
+    /// Taken from the authority of an absolute-form target, like `http://device.local/`,
+    /// otherwise from the `Host` header.
+    pub fn host(&self) -> Option<&'a str> {
+        self.authority
+            .or_else(|| self.headers.get_first("Host"))
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+    }
+
+    pub(crate) fn set_route_path(&mut self, route_path: &'a str) {
+        self.route_path = route_path;
+    }
+
     fn with_extracted_path<P2>(self, extracted_path: P2) -> Parts<'a, P2> {
         Parts {
             method: self.method,
             path: self.path,
             query: self.query,
             headers: self.headers,
+            authority: self.authority,
+            route_method: self.route_method,
             extracted_path,
+            route_path: self.route_path,
+            route_candidates: self.route_candidates,
+            allowed_methods: self.allowed_methods,
         }
     }
 }
@@ -114,9 +160,6 @@ pub(crate) fn record_header_indices(
     let bytes_ptr = bytes.as_ptr() as usize;
 
     for (header, indices) in headers.iter().zip(indices.iter_mut()) {
-        if header.name.len() >= (1 << 16) {
-            todo!("return error too large");
-        }
         let name_start = header.name.as_ptr() as usize - bytes_ptr;
         let name_end = name_start + header.name.len();
         let value_start = header.value.as_ptr() as usize - bytes_ptr;
@@ -130,39 +173,236 @@ pub(crate) fn record_header_indices(
 }
 
 pub struct Body<'a, R> {
-    content_length: usize,
-    buf: &'a [u8],
+    state: &'a mut BodyState,
+    /// The part of the header buffer after the headers.
+    buf: &'a mut [u8],
     reader: R,
 }
 
-impl<'a, R: Read> Body<'a, R> {
-    pub(crate) fn new(content_length: usize, buf: &'a [u8], reader: R) -> Self {
+/// Framing state of a request body, which outlives the [`Body`] handed to the handler.
+///
+/// Allows the connection to drain the unread remainder of the body and to find
+/// the start of a pipelined request.
+pub(crate) struct BodyState {
+    framing: Framing,
+    /// Amount of bytes of the buffer consumed by the body.
+    consumed: usize,
+    /// Amount of bytes read into the buffer.
+    filled: usize,
+}
+
+pub(crate) enum Framing {
+    /// Remaining length of a body with a `Content-Length`.
+    Length(usize),
+    Chunked(ChunkedDecoder),
+}
+
+impl Framing {
+    /// Determines the framing of a request body, see
+    /// [RFC 9112, Section 6.3](https://www.rfc-editor.org/rfc/rfc9112#section-6.3).
+    pub fn from_headers(headers: &Headers<'_>) -> Result<Self, ProtocolError> {
+        let mut content_length = None;
+        let mut transfer_encoding = None;
+
+        for (name, value) in headers.iter() {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                // Only digits are allowed, unlike in the `usize` parser, which accepts a `+`.
+                let value = value.trim();
+                let length = match value.bytes().all(|b| b.is_ascii_digit()) {
+                    true => value.parse::<usize>().ok(),
+                    false => None,
+                }
+                .ok_or(ProtocolError::InvalidContentLength)?;
+                if content_length.is_some_and(|previous| previous != length) {
+                    return Err(ProtocolError::InvalidContentLength);
+                }
+                content_length = Some(length);
+            } else if name.eq_ignore_ascii_case("Transfer-Encoding") {
+                for coding in value.split(',').map(str::trim) {
+                    // Only the final coding may be chunked and no other coding is supported.
+                    if transfer_encoding.is_some() || !coding.eq_ignore_ascii_case("chunked") {
+                        return Err(ProtocolError::UnsupportedTransferCoding);
+                    }
+                    transfer_encoding = Some(coding);
+                }
+            }
+        }
+
+        match (content_length, transfer_encoding) {
+            // A sender must not send both, a smuggled request would otherwise be possible.
+            (Some(_), Some(_)) => Err(ProtocolError::ConflictingFraming),
+            (_, Some(_)) => Ok(Self::Chunked(ChunkedDecoder::new())),
+            (length, None) => Ok(Self::Length(length.unwrap_or(0))),
+        }
+    }
+}
+
+impl BodyState {
+    /// Creates the state of a body, `filled` bytes of it were read together with the headers.
+    pub fn new(framing: Framing, filled: usize) -> Self {
         Self {
-            content_length,
-            buf,
-            reader,
+            framing,
+            consumed: 0,
+            filled,
+        }
+    }
+
+    /// Returns `true` if the entire body has been read.
+    pub fn is_complete(&self) -> bool {
+        match &self.framing {
+            Framing::Length(remaining) => *remaining == 0,
+            Framing::Chunked(decoder) => decoder.is_done(),
+        }
+    }
+
+    /// The range of the buffer read past the end of the body, the start of
+    /// the next request.
+    pub fn pipelined(&self) -> core::ops::Range<usize> {
+        self.consumed..self.filled
+    }
+}
+
+impl<'a, R: Read> Body<'a, R> {
+    pub(crate) fn new(state: &'a mut BodyState, buf: &'a mut [u8], reader: R) -> Self {
+        Self { state, buf, reader }
+    }
+
+    /// Reads from the buffer first, then from the connection.
+    async fn read_raw(&mut self, buf: &mut [u8]) -> Result<usize, R::Error> {
+        // The buffer may contain more than the body, e.g. the next pipelined request,
+        // the caller must not read past the end of the body.
+        let prefetched = &self.buf[self.state.consumed..self.state.filled];
+        if !prefetched.is_empty() {
+            let read = buf.len().min(prefetched.len());
+            buf[..read].copy_from_slice(&prefetched[..read]);
+            self.state.consumed += read;
+            Ok(read)
+        } else {
+            self.reader.read(buf).await
+        }
+    }
+
+    async fn read_chunked(&mut self, buf: &mut [u8]) -> Result<usize, BodyError<R::Error>> {
+        loop {
+            let decoder = self.decoder();
+            if decoder.is_done() || buf.is_empty() {
+                return Ok(0);
+            }
+
+            if let Some(remaining) = decoder.data() {
+                let len = remaining.min(buf.len());
+                let read = self.read_raw(&mut buf[..len]).await?;
+                if read == 0 {
+                    return Err(BodyError::UnexpectedEof);
+                }
+                self.decoder().consume_data(read);
+                return Ok(read);
+            }
+
+            self.decode_framing().await?;
+        }
+    }
+
+    /// Decodes chunk sizes, extensions and trailers until chunk data follows
+    /// or the body ends.
+    ///
+    /// The framing is read into the buffer, what is read past the end of the body
+    /// stays in the buffer for the next request.
+    async fn decode_framing(&mut self) -> Result<(), BodyError<R::Error>> {
+        if self.state.consumed == self.state.filled {
+            // Everything in the buffer was consumed, it can be reused.
+            self.state.consumed = 0;
+            self.state.filled = 0;
+
+            // Without space left after the headers, the framing is read byte by byte.
+            let mut byte = [0u8];
+            let buf = match self.buf.is_empty() {
+                true => &mut byte[..],
+                false => &mut *self.buf,
+            };
+            let read = self.reader.read(buf).await?;
+            if read == 0 {
+                return Err(BodyError::UnexpectedEof);
+            }
+            if self.buf.is_empty() {
+                return self.decode(byte[0]);
+            }
+            self.state.filled = read;
+        }
+
+        while self.state.consumed < self.state.filled {
+            let decoder = self.decoder();
+            if decoder.is_done() || decoder.data().is_some() {
+                break;
+            }
+            self.decode(self.buf[self.state.consumed])?;
+            self.state.consumed += 1;
+        }
+        Ok(())
+    }
+
+    fn decode(&mut self, byte: u8) -> Result<(), BodyError<R::Error>> {
+        self.decoder()
+            .decode(byte)
+            .map_err(|InvalidChunk| BodyError::InvalidChunk)
+    }
+
+    fn decoder(&mut self) -> &mut ChunkedDecoder {
+        match &mut self.state.framing {
+            Framing::Chunked(decoder) => decoder,
+            Framing::Length(..) => unreachable!("body is not chunked"),
         }
     }
 }
 
 impl<'a, R: ErrorType> ErrorType for Body<'a, R> {
-    type Error = R::Error;
+    type Error = BodyError<R::Error>;
 }
 
 impl<'a, R: Read> Read for Body<'a, R> {
-    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, R::Error> {
-        if self.content_length == 0 {
-            return Ok(0);
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match self.state.framing {
+            Framing::Length(remaining) => {
+                let len = remaining.min(buf.len());
+                if len == 0 {
+                    return Ok(0);
+                }
+
+                let read = self.read_raw(&mut buf[..len]).await?;
+                if read == 0 {
+                    return Err(BodyError::UnexpectedEof);
+                }
+                self.state.framing = Framing::Length(remaining - read);
+                Ok(read)
+            }
+            Framing::Chunked(_) => self.read_chunked(buf).await,
         }
+    }
+}
 
-        let read = if !self.buf.is_empty() {
-            let mut reader = &self.buf[..self.content_length.min(self.buf.len())];
-            reader.read(buf).await.unwrap_or_else(|x| match x {})
-        } else {
-            self.reader.read(buf).await?
-        };
+/// Error returned when reading the request [`Body`].
+#[derive(Debug)]
+pub enum BodyError<E> {
+    /// Reading from the connection failed.
+    Io(E),
+    /// The body uses a malformed chunked transfer coding.
+    InvalidChunk,
+    /// The connection was closed before the end of the body.
+    UnexpectedEof,
+}
 
-        self.content_length -= read;
-        Ok(read)
+impl<E> From<E> for BodyError<E> {
+    fn from(err: E) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl<E: embedded_io_async::Error> embedded_io_async::Error for BodyError<E> {
+    fn kind(&self) -> embedded_io_async::ErrorKind {
+        match self {
+            Self::Io(err) => err.kind(),
+            Self::InvalidChunk => embedded_io_async::ErrorKind::InvalidData,
+            Self::UnexpectedEof => embedded_io_async::ErrorKind::ConnectionAborted,
+        }
     }
 }
@@ -1,35 +1,131 @@
-use crate::{http::StatusCode, Read};
+use crate::{
+    either::Either,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    Read,
+};
 
-pub struct Response<Body> {
-    // TODO: headers
+pub struct Response<B> {
     pub status_code: StatusCode,
-    pub body: Body,
+    pub headers: HeaderMap,
+    pub body: B,
 }
 
-impl<Body> Response<Body> {
+impl<B> Response<B> {
     pub fn status_code(&self) -> StatusCode {
         self.status_code
     }
 
-    pub fn into_body(self) -> Body {
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    pub fn into_body(self) -> B {
         self.body
     }
 }
 
-impl<Body> Response<Body> {
+impl<B> Response<B> {
     pub(crate) fn map_body<F, T>(self, map: F) -> Response<T>
     where
-        F: FnOnce(Body) -> T,
+        F: FnOnce(B) -> T,
     {
         Response {
             status_code: self.status_code,
+            headers: self.headers,
             body: map(self.body),
         }
     }
 }
 
+/// A response body.
+pub trait Body: Read {
+    /// Returns the bounds on the remaining length of the body.
+    ///
+    /// When the exact length is known, it is sent as the `Content-Length`.
+    fn size_hint(&self) -> SizeHint;
+}
+
+/// Bounds on the remaining length of a [`Body`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeHint {
+    lower: usize,
+    upper: Option<usize>,
+}
+
+impl SizeHint {
+    /// Creates a size hint without any known bounds.
+    pub const fn new() -> Self {
+        Self {
+            lower: 0,
+            upper: None,
+        }
+    }
+
+    /// Creates a size hint for a body of exactly `len` bytes.
+    pub const fn with_exact(len: usize) -> Self {
+        Self {
+            lower: len,
+            upper: Some(len),
+        }
+    }
+
+    pub fn lower(&self) -> usize {
+        self.lower
+    }
+
+    pub fn upper(&self) -> Option<usize> {
+        self.upper
+    }
+
+    /// Sets the lower bound.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lower` is greater than the upper bound.
+    pub fn set_lower(&mut self, lower: usize) {
+        assert!(self.upper.is_none_or(|upper| lower <= upper));
+        self.lower = lower;
+    }
+
+    /// Sets the upper bound.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `upper` is smaller than the lower bound.
+    pub fn set_upper(&mut self, upper: usize) {
+        assert!(upper >= self.lower);
+        self.upper = Some(upper);
+    }
+
+    /// Returns the exact length, if the lower and upper bound are the same.
+    pub fn exact(&self) -> Option<usize> {
+        self.upper.filter(|&upper| upper == self.lower)
+    }
+
+    pub fn set_exact(&mut self, len: usize) {
+        self.lower = len;
+        self.upper = Some(len);
+    }
+}
+
+impl Default for SizeHint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Body for &[u8] {
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.len())
+    }
+}
+
 pub trait IntoResponse {
-    type Body: Read; // TODO: this should probably be a Body trait (with content-length?)
+    type Body: Body;
 
     fn into_response(self) -> Response<Self::Body>;
 }
@@ -42,11 +138,11 @@ impl IntoResponse for core::convert::Infallible {
     }
 }
 
-impl<Body: Read> IntoResponse for Response<Body>
+impl<B: Body> IntoResponse for Response<B>
 where
-    Body: 'static,
+    B: 'static,
 {
-    type Body = Body;
+    type Body = B;
 
     fn into_response(self) -> Response<Self::Body> {
         self
@@ -57,8 +153,15 @@ impl IntoResponse for &'static str {
     type Body = &'static [u8];
 
     fn into_response(self) -> Response<Self::Body> {
+        let mut headers = HeaderMap::new();
+        let _ = headers.insert(
+            const { HeaderName::from_static("Content-Type") },
+            const { HeaderValue::from_static("text/plain; charset=utf-8") },
+        );
+
         Response {
             status_code: StatusCode::OK,
+            headers,
             body: self.as_bytes(),
         }
     }
@@ -74,10 +177,49 @@ impl<T: IntoResponse> IntoResponse for (StatusCode, T) {
     }
 }
 
+impl<T: IntoResponse, const N: usize> IntoResponse for ([(&'static str, &'static str); N], T) {
+    type Body = Either<T::Body, &'static [u8]>;
+
+    fn into_response(self) -> Response<Self::Body> {
+        let mut response = self.1.into_response();
+        for (name, value) in self.0 {
+            let error = match (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+                (Ok(name), Ok(value)) => match response.headers.insert(name, value) {
+                    Ok(_) => continue,
+                    Err(_) => "Too many response headers",
+                },
+                _ => "Invalid response header",
+            };
+            return (StatusCode::INTERNAL_SERVER_ERROR, error)
+                .into_response()
+                .map_body(Either::Right);
+        }
+        response.map_body(Either::Left)
+    }
+}
+
+impl<T: IntoResponse, const N: usize> IntoResponse
+    for (StatusCode, [(&'static str, &'static str); N], T)
+{
+    type Body = Either<T::Body, &'static [u8]>;
+
+    fn into_response(self) -> Response<Self::Body> {
+        let mut response = (self.1, self.2).into_response();
+        if let Either::Left(_) = response.body {
+            response.status_code = self.0;
+        }
+        response
+    }
+}
+
 impl IntoResponse for () {
     type Body = &'static [u8];
 
     fn into_response(self) -> Response<Self::Body> {
-        (StatusCode::OK, "").into_response()
+        Response {
+            status_code: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: b"",
+        }
     }
 }
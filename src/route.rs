@@ -1,20 +1,18 @@
-use core::future::Future;
+use core::{future::Future, marker::PhantomData};
 
-use crate::{either::Either, handler, http, IntoResponse, PathSegments, Read, Request, Response};
+use crate::{
+    dispatch::RouteTable, either::Either, guard::Guarded, handler::HandlerFunctionHandlerAdapter,
+    http, FromRef, HostPattern, IntoResponse, InvalidEncoding, Parts, PathSegments, Pattern, Read,
+    Request, Response,
+};
 
 macro_rules! impl_handler_func {
     ($name:ident, $method:ident) => {
-        pub fn $name<H, S, P, FuncParams>(handler: H) -> impl Route<S, P>
-        where
-            H: handler::HandlerFunction<S, P, FuncParams>,
-        {
-            Method {
-                method: $crate::http::Method::$method,
-                route: $crate::handler::HandlerFunctionHandlerAdapter {
-                    handler,
-                    _params: Default::default(),
-                },
-            }
+        #[doc = concat!("Routes `", stringify!($method), "` requests to the handler, see [`MethodRouter`].")]
+        pub fn $name<H, FuncParams>(
+            handler: H,
+        ) -> MethodRouter<Method<HandlerFunctionHandlerAdapter<FuncParams, H>>> {
+            on($crate::http::Method::$method, handler)
         }
     };
 }
@@ -29,6 +27,40 @@ impl_handler_func!(connect, CONNECT);
 impl_handler_func!(patch, PATCH);
 impl_handler_func!(trace, TRACE);
 
+/// Routes requests with the `method` to the handler, see [`MethodRouter`].
+///
+/// Extension methods can be created with [`http::Method::from_static`].
+pub fn on<H, FuncParams>(
+    method: http::Method<'static>,
+    handler: H,
+) -> MethodRouter<Method<HandlerFunctionHandlerAdapter<FuncParams, H>>> {
+    let mut methods = http::MethodSet::new();
+    methods.insert(method);
+
+    MethodRouter {
+        route: method_route(method, handler),
+        methods,
+    }
+}
+
+fn method_route<H, FuncParams>(
+    method: http::Method<'static>,
+    handler: H,
+) -> Method<HandlerFunctionHandlerAdapter<FuncParams, H>> {
+    Method {
+        method,
+        route: HandlerFunctionHandlerAdapter {
+            handler,
+            _params: Default::default(),
+        },
+    }
+}
+
+/// The result of matching a request against a [`Route`].
+///
+/// A request which is not matched is handed back to be matched by the next route.
+// It cannot be boxed without `alloc`.
+#[allow(clippy::large_enum_variant)]
 pub enum Decision<'a, T, R, P> {
     Match(T),
     NoMatch(Request<'a, R, P>),
@@ -61,14 +93,53 @@ impl<'a, T, R, P> Decision<'a, T, R, P> {
     }
 }
 
+/// Matches requests and responds to them, routes are chained with [`Router::route`](crate::Router::route).
 pub trait Route<S, P = ()> {
     type Response: IntoResponse;
 
     fn match_request<'a, Body: Read>(
         &'a self,
         req: Request<'a, Body, P>,
-        state: &'a S,
+        state: &S,
     ) -> impl Future<Output = Decision<'a, Self::Response, Body, P>>;
+
+    /// Adds the methods this route responds to, used to answer `OPTIONS *`.
+    fn allowed_methods(&self, methods: &mut http::MethodSet) {
+        let _ = methods;
+    }
+
+    /// The amount of routes in a chain of routes, `1` for a single route.
+    fn routes(&self) -> usize {
+        1
+    }
+
+    /// Calls `f` with the index of every route in the chain, starting at `index`, and the
+    /// first segment of the paths it may match, see [`PathSegments::first_segment`].
+    ///
+    /// A route may report several segments, `None` if it may match any first segment.
+    /// The router only tries the route for requests with one of them.
+    fn first_segments(&self, index: usize, f: &mut dyn FnMut(usize, Option<&'static str>)) {
+        f(index, None);
+    }
+
+    /// Whether the route matches the `path` for any method, without calling a handler.
+    ///
+    /// Used to redirect to the other form of a path with a trailing slash only if it
+    /// matches a route, see [`TrailingSlash`](crate::TrailingSlash). Routes which
+    /// do not match paths return `false`.
+    fn matches_path(&self, path: &str, parts: &Parts<'_, P>) -> bool {
+        let _ = (path, parts);
+        false
+    }
+
+    /// Calls `f` with the pattern and methods of every route which unconditionally
+    /// matches a path, used to detect conflicting routes, see [`Pattern`].
+    ///
+    /// `prefix` is the pattern of the enclosing path or nest prefix. Routes which
+    /// may fall through for other reasons do not report anything.
+    fn visit_routes(&self, prefix: &Pattern, f: &mut dyn FnMut(&Pattern, &http::MethodSet)) {
+        let _ = (prefix, f);
+    }
 }
 
 // impl<S, T: Handler<S>> Route<S> for T {
@@ -81,6 +152,31 @@ pub trait Route<S, P = ()> {
 //     }
 // }
 
+/// A route which never matches, the start of every route chain.
+pub struct Empty;
+
+impl<S, P> Route<S, P> for Empty {
+    type Response = core::convert::Infallible;
+
+    async fn match_request<'a, Body: Read>(
+        &'a self,
+        req: Request<'a, Body, P>,
+        _state: &S,
+    ) -> Decision<'a, Self::Response, Body, P> {
+        Decision::NoMatch(req)
+    }
+
+    fn routes(&self) -> usize {
+        0
+    }
+
+    fn first_segments(&self, _index: usize, _f: &mut dyn FnMut(usize, Option<&'static str>)) {}
+}
+
+/// Responds with `404 Not Found`, unless a route matched the path but not the method.
+///
+/// Then `OPTIONS` requests are answered with the allowed methods and all other
+/// requests with `405 Method Not Allowed`.
 pub struct NotFound;
 
 impl<S, P> Route<S, P> for NotFound {
@@ -88,10 +184,27 @@ impl<S, P> Route<S, P> for NotFound {
 
     async fn match_request<'a, Body: Read>(
         &'a self,
-        _req: Request<'a, Body, P>,
-        _state: &'a S,
+        req: Request<'a, Body, P>,
+        _state: &S,
     ) -> Decision<'a, Self::Response, Body, P> {
-        Decision::Match((http::StatusCode::NOT_FOUND, "Not Found").into_response())
+        let options = req.method() == http::Method::OPTIONS;
+        let mut allowed = req.parts.allowed_methods;
+        if allowed.is_empty() {
+            return Decision::Match((http::StatusCode::NOT_FOUND, "Not Found").into_response());
+        }
+        allowed.insert(http::Method::OPTIONS);
+
+        let mut response = match options {
+            true => ().into_response(),
+            false => (http::StatusCode::METHOD_NOT_ALLOWED, "Method Not Allowed").into_response(),
+        };
+        // The methods of all routes were checked to fit when the router was built.
+        if let Ok(value) = http::HeaderValue::from_display(&allowed) {
+            let _ = response
+                .headers
+                .insert(const { http::HeaderName::from_static("Allow") }, value);
+        }
+        Decision::Match(response)
     }
 }
 
@@ -100,25 +213,203 @@ pub struct Path<P, R> {
     pub(crate) route: R,
 }
 
-impl<S, P: PathSegments, R: Route<S, P::Output>> Route<S, ()> for Path<P, R> {
-    type Response = R::Response;
+impl<P: PathSegments, R> Path<P, R> {
+    pub(crate) fn new(path: P, route: R) -> Self {
+        Self { path, route }
+    }
+}
+
+impl<S, P, R, T> Route<S, ()> for Path<P, R>
+where
+    P: PathSegments,
+    R: for<'p> Route<S, P::Output<'p>, Response = T>,
+    T: IntoResponse,
+{
+    type Response = Either<T, InvalidEncoding>;
 
     async fn match_request<'a, Body: Read>(
         &'a self,
         req: Request<'a, Body, ()>,
-        state: &'a S,
+        state: &S,
     ) -> Decision<'a, Self::Response, Body, ()> {
-        if let Some(path) = self.path.parse(req.path()) {
-            self.route
+        match self.path.try_parse(req.parts.route_path) {
+            Ok(Some(path)) => match self
+                .route
                 .match_request(req.with_extracted_path(path), state)
                 .await
                 .erase()
-        } else {
-            Decision::NoMatch(req)
+            {
+                Decision::Match(response) => Decision::Match(Either::Left(response)),
+                Decision::NoMatch(req) => Decision::NoMatch(req),
+            },
+            Ok(None) => Decision::NoMatch(req),
+            Err(err) => Decision::Match(Either::Right(err)),
+        }
+    }
+
+    fn allowed_methods(&self, methods: &mut http::MethodSet) {
+        self.route.allowed_methods(methods);
+    }
+
+    fn first_segments(&self, index: usize, f: &mut dyn FnMut(usize, Option<&'static str>)) {
+        f(index, self.path.first_segment());
+    }
+
+    fn matches_path(&self, path: &str, _parts: &Parts<'_, ()>) -> bool {
+        self.path.parse(path).is_some()
+    }
+
+    fn visit_routes(&self, prefix: &Pattern, f: &mut dyn FnMut(&Pattern, &http::MethodSet)) {
+        let mut pattern = Pattern::new();
+        if !self.path.pattern(&mut pattern) {
+            return;
+        }
+        if let Some(pattern) = prefix.join(&pattern) {
+            self.route.visit_routes(&pattern, f);
+        }
+    }
+}
+
+/// Matches the routes of a nested router against the path after the `prefix`.
+pub struct Nest<R, S> {
+    pub(crate) prefix: &'static str,
+    pub(crate) route: R,
+    pub(crate) _state: PhantomData<fn() -> S>,
+    table: RouteTable,
+}
+
+impl<R, S> Nest<R, S> {
+    pub(crate) fn new(prefix: &'static str, route: R) -> Self
+    where
+        R: Route<S>,
+    {
+        Self {
+            prefix,
+            table: RouteTable::new(&route),
+            route,
+            _state: PhantomData,
+        }
+    }
+
+    /// Returns the path after the prefix, the root path if nothing follows it.
+    fn strip_prefix<'a>(&self, path: &'a str) -> Option<&'a str> {
+        match path.strip_prefix(self.prefix) {
+            Some("") => Some("/"),
+            Some(rest) if rest.starts_with('/') => Some(rest),
+            _ => None,
+        }
+    }
+}
+
+impl<S, S2: FromRef<S>, R: Route<S2>> Route<S, ()> for Nest<R, S2> {
+    type Response = R::Response;
+
+    async fn match_request<'a, Body: Read>(
+        &'a self,
+        mut req: Request<'a, Body, ()>,
+        state: &S,
+    ) -> Decision<'a, Self::Response, Body, ()> {
+        let path = req.parts.route_path;
+        let Some(rest) = self.strip_prefix(path) else {
+            return Decision::NoMatch(req);
+        };
+
+        req.parts.set_route_path(rest);
+        let candidates = req.parts.route_candidates;
+        req.parts.route_candidates = self.table.candidates(rest);
+        match self.route.match_request(req, &S2::from_ref(state)).await {
+            Decision::Match(response) => Decision::Match(response),
+            Decision::NoMatch(mut req) => {
+                req.parts.set_route_path(path);
+                req.parts.route_candidates = candidates;
+                Decision::NoMatch(req)
+            }
+        }
+    }
+
+    fn allowed_methods(&self, methods: &mut http::MethodSet) {
+        self.route.allowed_methods(methods);
+    }
+
+    fn first_segments(&self, index: usize, f: &mut dyn FnMut(usize, Option<&'static str>)) {
+        f(index, self.prefix.first_segment());
+    }
+
+    fn matches_path(&self, path: &str, parts: &Parts<'_, ()>) -> bool {
+        self.strip_prefix(path)
+            .is_some_and(|rest| self.route.matches_path(rest, parts))
+    }
+
+    fn visit_routes(&self, prefix: &Pattern, f: &mut dyn FnMut(&Pattern, &http::MethodSet)) {
+        let mut pattern = prefix.clone();
+        if pattern.push_literal(self.prefix) {
+            self.route.visit_routes(&pattern, f);
         }
     }
 }
 
+/// Matches the routes of another router, only for requests to a host matching the `pattern`.
+pub struct Host<R, S> {
+    pub(crate) pattern: HostPattern,
+    pub(crate) route: R,
+    pub(crate) _state: PhantomData<fn() -> S>,
+    table: RouteTable,
+}
+
+impl<R, S> Host<R, S> {
+    pub(crate) fn new(pattern: HostPattern, route: R) -> Self
+    where
+        R: Route<S>,
+    {
+        Self {
+            pattern,
+            table: RouteTable::new(&route),
+            route,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S, S2: FromRef<S>, P, R: Route<S2, P>> Route<S, P> for Host<R, S2> {
+    type Response = R::Response;
+
+    async fn match_request<'a, Body: Read>(
+        &'a self,
+        mut req: Request<'a, Body, P>,
+        state: &S,
+    ) -> Decision<'a, Self::Response, Body, P> {
+        match req.parts.host() {
+            Some(host) if self.pattern.matches(host) => {
+                let candidates = req.parts.route_candidates;
+                req.parts.route_candidates = self.table.candidates(req.parts.route_path);
+                match self.route.match_request(req, &S2::from_ref(state)).await {
+                    Decision::Match(response) => Decision::Match(response),
+                    Decision::NoMatch(mut req) => {
+                        req.parts.route_candidates = candidates;
+                        Decision::NoMatch(req)
+                    }
+                }
+            }
+            _ => Decision::NoMatch(req),
+        }
+    }
+
+    fn allowed_methods(&self, methods: &mut http::MethodSet) {
+        self.route.allowed_methods(methods);
+    }
+
+    /// The first segments of all routes of the other router.
+    fn first_segments(&self, index: usize, f: &mut dyn FnMut(usize, Option<&'static str>)) {
+        self.route
+            .first_segments(0, &mut |_, segment| f(index, segment));
+    }
+
+    fn matches_path(&self, path: &str, parts: &Parts<'_, P>) -> bool {
+        parts.host().is_some_and(|host| self.pattern.matches(host))
+            && self.route.matches_path(path, parts)
+    }
+}
+
 pub struct Method<R> {
     pub(crate) method: http::Method<'static>,
     pub(crate) route: R,
@@ -130,9 +421,9 @@ impl<S, P, R: Route<S, P>> Route<S, P> for Method<R> {
     async fn match_request<'a, Body: Read>(
         &'a self,
         req: Request<'a, Body, P>,
-        state: &'a S,
+        state: &S,
     ) -> Decision<'a, Self::Response, Body, P> {
-        if self.method == req.method() {
+        if self.method == req.parts.route_method {
             self.route.match_request(req, state).await
         } else {
             Decision::NoMatch(req)
@@ -140,9 +431,132 @@ impl<S, P, R: Route<S, P>> Route<S, P> for Method<R> {
     }
 }
 
+/// Dispatches a request, which already matched a path, on its method.
+///
+/// Created with [`get`], [`post`], etc. and extended by chaining further
+/// methods, like `get(a).post(b).delete(c)`. Registering the same method twice
+/// panics in debug builds, otherwise the first handler wins.
+pub struct MethodRouter<R> {
+    route: R,
+    methods: http::MethodSet,
+}
+
+macro_rules! impl_method_router_func {
+    ($name:ident, $method:ident) => {
+        #[doc = concat!("Additionally routes `", stringify!($method), "` requests to the handler.")]
+        pub fn $name<H, FuncParams>(
+            self,
+            handler: H,
+        ) -> MethodRouter<Fallback<R, Method<HandlerFunctionHandlerAdapter<FuncParams, H>>>> {
+            self.on($crate::http::Method::$method, handler)
+        }
+    };
+}
+
+impl<R> MethodRouter<R> {
+    impl_method_router_func!(get, GET);
+    impl_method_router_func!(post, POST);
+    impl_method_router_func!(put, PUT);
+    impl_method_router_func!(delete, DELETE);
+    impl_method_router_func!(head, HEAD);
+    impl_method_router_func!(options, OPTIONS);
+    impl_method_router_func!(connect, CONNECT);
+    impl_method_router_func!(patch, PATCH);
+    impl_method_router_func!(trace, TRACE);
+
+    /// Only matches requests accepted by the `guard`, other requests fall through
+    /// to the next route, see [`guard`](crate::guard).
+    pub fn guard<G>(self, guard: G) -> Guarded<G, Self> {
+        Guarded::new(guard, self)
+    }
+
+    /// Additionally routes requests with the `method` to the handler.
+    pub fn on<H, FuncParams>(
+        mut self,
+        method: http::Method<'static>,
+        handler: H,
+    ) -> MethodRouter<Fallback<R, Method<HandlerFunctionHandlerAdapter<FuncParams, H>>>> {
+        debug_assert!(
+            !self.methods.contains(method),
+            "`{method}` is already routed, the handler would be unreachable"
+        );
+        self.methods.insert(method);
+
+        MethodRouter {
+            methods: self.methods,
+            route: Fallback::unfiltered(self.route, method_route(method, handler)),
+        }
+    }
+}
+
+impl<S, P, R: Route<S, P>> Route<S, P> for MethodRouter<R> {
+    type Response = R::Response;
+
+    async fn match_request<'a, Body: Read>(
+        &'a self,
+        req: Request<'a, Body, P>,
+        state: &S,
+    ) -> Decision<'a, Self::Response, Body, P> {
+        match self.route.match_request(req, state).await {
+            Decision::Match(response) => Decision::Match(response),
+            Decision::NoMatch(mut req) => {
+                // The path matched, remember the methods for a `405 Method Not Allowed`.
+                self.allowed_methods(&mut req.parts.allowed_methods);
+                Decision::NoMatch(req)
+            }
+        }
+    }
+
+    /// `HEAD` is allowed with a `GET` handler, the router answers `HEAD` requests
+    /// without a `HEAD` route with it.
+    fn allowed_methods(&self, methods: &mut http::MethodSet) {
+        methods.extend(&self.methods);
+        if self.methods.contains(http::Method::GET) {
+            methods.insert(http::Method::HEAD);
+        }
+    }
+
+    /// Only reports the registered methods, a `HEAD` route takes precedence over
+    /// an implicit `HEAD` of a `GET` handler.
+    fn visit_routes(&self, prefix: &Pattern, f: &mut dyn FnMut(&Pattern, &http::MethodSet)) {
+        f(prefix, &self.methods);
+    }
+}
+
+/// Tries the `route` first and then the `fallback`.
+///
+/// Routes are chained by [`Router::route`](crate::Router::route), the routes of a chain
+/// are numbered from the newest one. A router looks up the routes which may match the
+/// first segment of a request path once, see [`Route::first_segments`]. Either side is
+/// then skipped without matching it, when none of its routes is a candidate.
 pub struct Fallback<T, S> {
-    pub(crate) route: T,
-    pub(crate) fallback: S,
+    route: T,
+    fallback: S,
+    /// The amount of routes on each side, `None` if the fallback is tried for every request.
+    routes: Option<(usize, usize)>,
+}
+
+impl<T, S> Fallback<T, S> {
+    pub(crate) fn new<St, P>(route: T, fallback: S) -> Self
+    where
+        T: Route<St, P>,
+        S: Route<St, P>,
+    {
+        Self {
+            routes: Some((route.routes(), fallback.routes())),
+            route,
+            fallback,
+        }
+    }
+
+    /// A chain which is never skipped, for routes which do not match the path.
+    fn unfiltered(route: T, fallback: S) -> Self {
+        Self {
+            route,
+            fallback,
+            routes: None,
+        }
+    }
 }
 
 impl<S, P, R1: Route<S, P>, R2: Route<S, P>> Route<S, P> for Fallback<R1, R2> {
@@ -151,15 +565,70 @@ impl<S, P, R1: Route<S, P>, R2: Route<S, P>> Route<S, P> for Fallback<R1, R2> {
     async fn match_request<'a, Body: Read>(
         &'a self,
         req: Request<'a, Body, P>,
-        state: &'a S,
+        state: &S,
     ) -> Decision<'a, Self::Response, Body, P> {
-        match self.route.match_request(req, state).await {
-            Decision::Match(t) => return Decision::Match(Either::Left(t)),
-            Decision::NoMatch(req) => self
-                .fallback
-                .match_request(req, state)
-                .await
-                .map(Either::Right),
+        let Some((routes, fallbacks)) = self.routes else {
+            return match self.route.match_request(req, state).await {
+                Decision::Match(t) => Decision::Match(Either::Left(t)),
+                Decision::NoMatch(req) => self
+                    .fallback
+                    .match_request(req, state)
+                    .await
+                    .map(Either::Right),
+            };
+        };
+
+        let candidates = req.parts.route_candidates;
+        let mut req = match candidates.any_of_first(routes) {
+            true => match self.route.match_request(req, state).await {
+                Decision::Match(t) => return Decision::Match(Either::Left(t)),
+                Decision::NoMatch(req) => req,
+            },
+            false => req,
+        };
+
+        let rest = candidates.skip(routes);
+        if !rest.any_of_first(fallbacks) {
+            return Decision::NoMatch(req);
+        }
+        req.parts.route_candidates = rest;
+        match self.fallback.match_request(req, state).await {
+            Decision::Match(t) => Decision::Match(Either::Right(t)),
+            Decision::NoMatch(mut req) => {
+                req.parts.route_candidates = candidates;
+                Decision::NoMatch(req)
+            }
+        }
+    }
+
+    fn allowed_methods(&self, methods: &mut http::MethodSet) {
+        self.route.allowed_methods(methods);
+        self.fallback.allowed_methods(methods);
+    }
+
+    fn routes(&self) -> usize {
+        match self.routes {
+            Some((routes, fallbacks)) => routes + fallbacks,
+            None => 1,
+        }
+    }
+
+    fn first_segments(&self, index: usize, f: &mut dyn FnMut(usize, Option<&'static str>)) {
+        match self.routes {
+            Some((routes, _)) => {
+                self.route.first_segments(index, f);
+                self.fallback.first_segments(index + routes, f);
+            }
+            None => f(index, None),
         }
     }
+
+    fn matches_path(&self, path: &str, parts: &Parts<'_, P>) -> bool {
+        self.route.matches_path(path, parts) || self.fallback.matches_path(path, parts)
+    }
+
+    fn visit_routes(&self, prefix: &Pattern, f: &mut dyn FnMut(&Pattern, &http::MethodSet)) {
+        self.route.visit_routes(prefix, f);
+        self.fallback.visit_routes(prefix, f);
+    }
 }
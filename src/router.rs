@@ -1,13 +1,19 @@
 use core::{marker::PhantomData, mem::MaybeUninit};
 
 use crate::{
-    error::ProtocolError,
-    handler, http,
-    parse::PathAndQuery,
-    request::{record_header_indices, Body, HeaderIndices, Headers, Parts},
-    route::{self, Route},
+    config::{Buffers, ServerConfig, TrailingSlash},
+    connection::{has_token, write_response, Connection},
+    dispatch::{Candidates, RouteTable},
+    either::Either,
+    error::{InvalidUrl, ProtocolError},
+    handler::{self, HandlerFunctionHandlerAdapter},
+    http,
+    parse::{merge_slashes, PathAndQuery},
+    request::{record_header_indices, Body, BodyError, BodyState, Framing, Headers, Parts},
+    route::{self, Decision, Route},
     service::ServiceError,
-    utils, ErrorType, IntoResponse, PathSegments, Read, Request, Service, Write,
+    ErrorType, FromRef, HostPattern, IntoResponse, PathSegments, Pattern, Read, Request, Response,
+    Service, Write,
 };
 
 mod private {
@@ -18,55 +24,71 @@ mod private {
     pub enum Untouched {}
 }
 
-pub struct Router<RS, R: Route<RS>, S = (), HasRoute = private::Untouched> {
+/// Response of a matched route, the `405 Method Not Allowed` response or the fallback.
+type RouterResponse<R, F> = Either<R, Either<Response<&'static [u8]>, F>>;
+
+pub struct Router<RS, R: Route<RS>, S = (), HasRoute = private::Untouched, F = route::NotFound> {
     state: S,
     route: R,
+    /// The routes of `route` by their first segment, rebuilt when a route is added.
+    table: RouteTable,
+    fallback: F,
+    config: ServerConfig,
     _priv: PhantomData<(RS, HasRoute)>,
 }
 
-impl<RS> Router<RS, route::NotFound> {
+impl<RS> Router<RS, route::Empty> {
     pub fn new() -> Self {
         Self {
             state: (),
-            route: route::NotFound,
+            route: route::Empty,
+            table: RouteTable::new::<RS, ()>(&route::Empty),
+            fallback: route::NotFound,
+            config: ServerConfig::new(),
             _priv: Default::default(),
         }
     }
 }
 
-impl<RS> Default for Router<RS, route::NotFound> {
+impl<RS> Default for Router<RS, route::Empty> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<R, S> Router<(), R, S, private::Untouched>
+impl<R, S, F> Router<(), R, S, private::Untouched, F>
 where
     R: Route<()>,
 {
-    pub fn with_state<S2>(self, state: S2) -> Router<S2, R, S2, private::HasAnyState>
+    pub fn with_state<S2>(self, state: S2) -> Router<S2, R, S2, private::HasAnyState, F>
     where
         R: Route<S2>,
     {
         Router {
             route: self.route,
+            table: self.table,
+            fallback: self.fallback,
             state,
+            config: self.config,
             _priv: Default::default(),
         }
     }
 }
 
-impl<RS, R, S> Router<RS, R, S, private::HasAnyState>
+impl<RS, R, S, F> Router<RS, R, S, private::HasAnyState, F>
 where
     R: Route<RS>,
 {
-    pub fn with_state<S2>(self, state: S2) -> Router<S2, R, S2, private::HasAnyState>
+    pub fn with_state<S2>(self, state: S2) -> Router<S2, R, S2, private::HasAnyState, F>
     where
         R: Route<S2>,
     {
         Router {
             route: self.route,
+            table: self.table,
+            fallback: self.fallback,
             state,
+            config: self.config,
             _priv: Default::default(),
         }
     }
@@ -74,18 +96,19 @@ where
 
 macro_rules! impl_method {
     ($method:ident) => {
-        impl<RS, R, S, HasRoute> Router<RS, R, S, HasRoute>
+        impl<RS, R, S, HasRoute, F> Router<RS, R, S, HasRoute, F>
         where
             R: Route<RS>,
         {
-            pub fn $method<P, H, X>(
+            pub fn $method<P, H, X, Res>(
                 self,
                 path: P,
                 handler: H,
-            ) -> Router<RS, impl Route<RS>, S, private::HasAnyState>
+            ) -> Router<RS, impl Route<RS>, S, private::HasAnyState, F>
             where
                 P: PathSegments,
-                H: handler::HandlerFunction<RS, P::Output, X>,
+                H: for<'p> handler::HandlerFunction<RS, P::Output<'p>, X, Response = Res>,
+                Res: IntoResponse,
             {
                 self.route(path, route::$method(handler))
             }
@@ -103,135 +126,2137 @@ impl_method!(connect);
 impl_method!(patch);
 impl_method!(trace);
 
-impl<RS, R, S, HasRoute> Router<RS, R, S, HasRoute>
+impl<RS, R, S, HasRoute, F> Router<RS, R, S, HasRoute, F>
 where
     R: Route<RS>,
 {
-    pub fn route<P: PathSegments, T: Route<RS, P::Output>>(
+    pub fn with_config(self, config: ServerConfig) -> Self {
+        Self { config, ..self }
+    }
+
+    /// Sets the handler for requests which do not match any route, instead of
+    /// responding with `404 Not Found`.
+    ///
+    /// Requests which match the path of a route, but not its method, are still
+    /// answered with `405 Method Not Allowed`.
+    pub fn fallback<H, X>(self, handler: H) -> Router<RS, R, S, HasRoute, impl Route<RS>>
+    where
+        H: handler::HandlerFunction<RS, (), X>,
+    {
+        Router {
+            route: self.route,
+            table: self.table,
+            fallback: HandlerFunctionHandlerAdapter {
+                handler,
+                _params: PhantomData,
+            },
+            state: self.state,
+            config: self.config,
+            _priv: Default::default(),
+        }
+    }
+
+    /// Adds a route for the `path`, routes added later take precedence.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if the [`Pattern`] of the route covers the pattern of an
+    /// existing route with a common method, it would be unreachable. Without the `alloc`
+    /// feature, panics if the routes use more than
+    /// [`MAX_EXTENSION_METHODS`](http::MAX_EXTENSION_METHODS) extension methods.
+    pub fn route<P, T, Res>(
         self,
         path: P,
         route: T,
-    ) -> Router<RS, impl Route<RS>, S, private::HasAnyState> {
+    ) -> Router<RS, impl Route<RS>, S, private::HasAnyState, F>
+    where
+        P: PathSegments,
+        T: for<'p> Route<RS, P::Output<'p>, Response = Res>,
+        Res: IntoResponse,
+    {
+        let route = route::Path::new(path, route);
+        assert_no_conflicts(&route, &self.route);
+
+        let route = route::Fallback::new(route, self.route);
+        assert_methods_fit(&route);
+
         Router {
-            route: route::Fallback {
-                route: route::Path { path, route },
-                fallback: self.route,
-            },
+            table: RouteTable::new(&route),
+            route,
+            fallback: self.fallback,
             state: self.state,
+            config: self.config,
+            _priv: Default::default(),
+        }
+    }
+
+    /// Adds all routes of another router with the same state type.
+    ///
+    /// Like routes added with [`Router::route`], the merged routes take precedence.
+    /// The state, fallback and configuration of the other router are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Like [`Router::route`], panics if a merged route conflicts with an existing route
+    /// or if the routes use too many extension methods.
+    pub fn merge<R2, S2, HasRoute2, F2>(
+        self,
+        router: Router<RS, R2, S2, HasRoute2, F2>,
+    ) -> Router<RS, impl Route<RS>, S, private::HasAnyState, F>
+    where
+        R2: Route<RS>,
+    {
+        assert_no_conflicts(&router.route, &self.route);
+
+        let route = route::Fallback::new(router.route, self.route);
+        assert_methods_fit(&route);
+
+        Router {
+            table: RouteTable::new(&route),
+            route,
+            fallback: self.fallback,
+            state: self.state,
+            config: self.config,
+            _priv: Default::default(),
+        }
+    }
+
+    /// Nests the routes of another router under the path `prefix`.
+    ///
+    /// The nested routes match against the rest of the path, extractors still see the
+    /// full path. The state of the nested routes is created from the state of this router,
+    /// the state, fallback and configuration of the nested router are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `prefix` does not start with a `/`, ends with a `/` or is the root.
+    /// Like [`Router::route`], panics if a nested route conflicts with an existing route
+    /// or if the routes use too many extension methods.
+    pub fn nest<RS2, R2, S2, HasRoute2, F2>(
+        self,
+        prefix: &'static str,
+        router: Router<RS2, R2, S2, HasRoute2, F2>,
+    ) -> Router<RS, impl Route<RS>, S, private::HasAnyState, F>
+    where
+        RS2: FromRef<RS>,
+        R2: Route<RS2>,
+    {
+        assert!(prefix.starts_with('/'), "nest prefix must start with a `/`");
+        assert!(
+            !prefix.ends_with('/'),
+            "nest prefix must not end with a `/`"
+        );
+
+        let route = route::Nest::new(prefix, router.route);
+        assert_no_conflicts(&route, &self.route);
+
+        let route = route::Fallback::new(route, self.route);
+        assert_methods_fit(&route);
+
+        Router {
+            table: RouteTable::new(&route),
+            route,
+            fallback: self.fallback,
+            state: self.state,
+            config: self.config,
+            _priv: Default::default(),
+        }
+    }
+
+    /// Adds all routes of another router, which only match requests to a host matching
+    /// the `pattern`, see [`HostPattern`].
+    ///
+    /// Like routes added with [`Router::route`], the routes take precedence, requests to
+    /// other hosts are matched by the existing routes. The state of the routes is created
+    /// from the state of this router, the state, fallback and configuration of the other
+    /// router are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `pattern` is invalid or, like [`Router::route`], if the routes use
+    /// too many extension methods.
+    pub fn host<RS2, R2, S2, HasRoute2, F2>(
+        self,
+        pattern: &'static str,
+        router: Router<RS2, R2, S2, HasRoute2, F2>,
+    ) -> Router<RS, impl Route<RS>, S, private::HasAnyState, F>
+    where
+        RS2: FromRef<RS>,
+        R2: Route<RS2>,
+    {
+        let route = route::Host::new(HostPattern::new(pattern), router.route);
+
+        let route = route::Fallback::new(route, self.route);
+        assert_methods_fit(&route);
+
+        Router {
+            table: RouteTable::new(&route),
+            route,
+            fallback: self.fallback,
+            state: self.state,
+            config: self.config,
             _priv: Default::default(),
         }
     }
 }
 
-impl<R: Route<S> + 'static, S, HasRoute> Service for Router<S, R, S, HasRoute> {
-    type BodyError = <<R::Response as IntoResponse>::Body as ErrorType>::Error;
+impl<R, S, HasRoute, F> Service for Router<S, R, S, HasRoute, F>
+where
+    R: Route<S> + 'static,
+    F: Route<S> + 'static,
+{
+    type BodyError =
+        <<RouterResponse<R::Response, F::Response> as IntoResponse>::Body as ErrorType>::Error;
 
     async fn serve<Re: Read, Wr: Write<Error = Re::Error>>(
         &self,
+        reader: Re,
+        writer: Wr,
+    ) -> Result<(), ServiceError<Re::Error, Self::BodyError>> {
+        let mut buffers = Buffers::<2048, 100, 1024>::new();
+        self.serve_with_buffers(&mut buffers, reader, writer).await
+    }
+}
+
+impl<R, S, HasRoute, F> Router<S, R, S, HasRoute, F>
+where
+    R: Route<S> + 'static,
+    F: Route<S> + 'static,
+{
+    /// Serves a connection, like [`Service::serve`], with caller provided buffers.
+    ///
+    /// The buffers only need to outlive the connection and can be reused for
+    /// the next one.
+    pub async fn serve_with_buffers<
+        Re: Read,
+        Wr: Write<Error = Re::Error>,
+        const HEADER: usize,
+        const MAX_HEADERS: usize,
+        const WRITE: usize,
+    >(
+        &self,
+        buffers: &mut Buffers<HEADER, MAX_HEADERS, WRITE>,
         mut reader: Re,
         mut writer: Wr,
-    ) -> Result<(), ServiceError<Re::Error, Self::BodyError>> {
-        // TODO: buf size, optinally make the buffer an arg
-        let mut buf = [0u8; 2048];
+    ) -> Result<(), ServiceError<Re::Error, <Self as Service>::BodyError>> {
+        let mut filled = 0;
+        for i in 1.. {
+            let last = i >= self.config.max_requests_per_connection;
 
-        const MAX_HEADERS: usize = 100;
+            match self
+                .serve_request(buffers, filled, &mut reader, &mut writer, last)
+                .await?
+            {
+                Connection::KeepAlive { pipelined } if !last => {
+                    filled = pipelined.len();
+                    buffers.header.copy_within(pipelined, 0);
+                }
+                _ => break,
+            }
+        }
 
-        let mut headers_indices: [MaybeUninit<HeaderIndices>; MAX_HEADERS] = unsafe {
-            // SAFETY: We can go safely from MaybeUninit array to array of MaybeUninit
-            MaybeUninit::uninit().assume_init()
-        };
+        Ok(())
+    }
 
-        let mut pos = 0;
-        let (method, path, headers, body_start) = loop {
-            // TODO check if buffer is full first
+    async fn serve_request<
+        Re: Read,
+        Wr: Write<Error = Re::Error>,
+        const HEADER: usize,
+        const MAX_HEADERS: usize,
+        const WRITE: usize,
+    >(
+        &self,
+        buffers: &mut Buffers<HEADER, MAX_HEADERS, WRITE>,
+        filled: usize,
+        mut reader: Re,
+        mut writer: Wr,
+        last: bool,
+    ) -> Result<Connection, ServiceError<Re::Error, <Self as Service>::BodyError>> {
+        let Buffers {
+            header: buf,
+            headers: headers_indices,
+            write: write_buf,
+        } = buffers;
+
+        // The buffer may already contain (the start of) a pipelined request.
+        let mut pos = filled;
+        let (method, target, version, headers_len, body_start) = loop {
+            if pos > 0 {
+                let mut headers: [MaybeUninit<httparse::Header<'_>>; MAX_HEADERS] =
+                    unsafe { MaybeUninit::uninit().assume_init() };
+                let mut req = httparse::Request::new(&mut []);
+
+                match req.parse_with_uninit_headers(&buf[..pos], &mut headers) {
+                    Ok(httparse::Status::Complete(len)) => {
+                        record_header_indices(buf, req.headers, headers_indices);
+
+                        // TODO: I think these unwraps cant happen, double check
+                        let target = range_of(buf, req.path.unwrap());
+                        // Common methods may be returned as static strings, the method
+                        // always precedes the target, separated by a single space.
+                        let method = target.start - 1 - req.method.unwrap().len();
+                        break (
+                            method..target.start - 1,
+                            target,
+                            req.version.unwrap(),
+                            req.headers.len(),
+                            len,
+                        );
+                    }
+                    Ok(httparse::Status::Partial) => {}
+                    Err(err) => {
+                        let err = ProtocolError::Parser(err);
+                        return self.reject(&mut writer, write_buf, err).await;
+                    }
+                }
+            }
+
+            if pos == buf.len() {
+                // Without a complete request line, the request target is too long.
+                let err = match buf.contains(&b'\n') {
+                    true => ProtocolError::HeadersTooLarge,
+                    false => ProtocolError::InvalidUrl(InvalidUrl::TooLong),
+                };
+                return self.reject(&mut writer, write_buf, err).await;
+            }
             let read = reader
                 .read(&mut buf[pos..])
                 .await
                 .map_err(ServiceError::Io)?;
             if read == 0 {
                 // TODO
-                return Ok(());
+                return Ok(Connection::Close);
             }
             pos += read;
+        };
+
+        // The request target is modified in place, it is not parsed again.
+        let mut target = target;
+        if self.config.merge_slashes {
+            target.end = target.start + merge_slashes(&mut buf[target.clone()]);
+        }
+        // The body is read into the rest of the buffer.
+        let (buf, body_buf) = buf.split_at_mut(body_start);
+        let buf = &*buf;
+        let headers = unsafe { headers_indices[..headers_len].assume_init_ref() };
+        // Both were validated by httparse and merging slashes keeps the target valid.
+        let method = core::str::from_utf8(&buf[method]).unwrap();
+        let path = core::str::from_utf8(&buf[target]).unwrap();
 
-            let mut headers: [MaybeUninit<httparse::Header<'_>>; MAX_HEADERS] =
-                unsafe { MaybeUninit::uninit().assume_init() };
-            let mut req = httparse::Request::new(&mut []);
+        let paq = match PathAndQuery::parse(path) {
+            Ok(paq) => paq,
+            Err(err) => {
+                return self
+                    .reject(&mut writer, write_buf, ProtocolError::InvalidUrl(err))
+                    .await
+            }
+        };
+        let method = match http::Method::new(method) {
+            Ok(method) => method,
+            Err(err) => {
+                return self
+                    .reject(&mut writer, write_buf, ProtocolError::InvalidMethod(err))
+                    .await
+            }
+        };
+        if paq.path() == "*" && method != http::Method::OPTIONS {
+            let err = ProtocolError::InvalidUrl(InvalidUrl::AsteriskForm);
+            return self.reject(&mut writer, write_buf, err).await;
+        }
+        let head = method == http::Method::HEAD;
+        let mut parts = Parts {
+            method,
+            path: paq.path(),
+            route_path: paq.path(),
+            route_candidates: Candidates::ALL,
+            query: paq.query(),
+            headers: Headers { headers, buf },
+            authority: paq.authority(),
+            route_method: method,
+            extracted_path: (),
+            allowed_methods: http::MethodSet::new(),
+        };
+        // `OPTIONS *` is answered with the methods of all routes, unless there is an
+        // explicit route for it.
+        if method == http::Method::OPTIONS && parts.path == "*" {
+            self.route.allowed_methods(&mut parts.allowed_methods);
+        }
 
-            match req.parse_with_uninit_headers(&buf, &mut headers) {
-                Ok(httparse::Status::Complete(len)) => {
-                    record_header_indices(&buf, req.headers, &mut headers_indices);
+        let framing = match Framing::from_headers(&parts.headers) {
+            Ok(framing) => framing,
+            Err(err) => return self.reject(&mut writer, write_buf, err).await,
+        };
 
-                    let headers = unsafe {
-                        MaybeUninit::slice_assume_init_ref(&headers_indices[..req.headers.len()])
-                    };
+        // HTTP/1.1 connections are persistent by default, HTTP/1.0 connections
+        // have to explicitly ask for it.
+        let connection = parts.headers.get_first("Connection");
+        let keep_alive = !last
+            && match version {
+                0 => connection.is_some_and(|value| has_token(value, "keep-alive")),
+                _ => !connection.is_some_and(|value| has_token(value, "close")),
+            };
 
-                    // TODO: I think these unwraps cant happen, double check
-                    break (req.method.unwrap(), req.path.unwrap(), headers, len);
+        // Holds the path with a trailing slash added, when matching it with
+        // `TrailingSlash::Ignore`.
+        let mut appended = None;
+        let mut body_state = BodyState::new(framing, pos - body_start);
+        let body = Body::new(&mut body_state, body_buf, &mut reader);
+        let request = Request::from_parts(parts, body);
+
+        let decision = match self.match_route(request).await {
+            Decision::NoMatch(mut request)
+                if self.config.trailing_slash == TrailingSlash::Ignore =>
+            {
+                match other_form(&request.parts, &mut appended) {
+                    Some(path) if self.route.matches_path(path, &request.parts) => {
+                        request.parts.set_route_path(path);
+                        self.match_route(request).await
+                    }
+                    _ => Decision::NoMatch(request),
                 }
-                Ok(httparse::Status::Partial) => {
-                    continue;
+            }
+            decision => decision,
+        };
+
+        let response = match decision {
+            Decision::Match(response) => Either::Left(response),
+            // The path matched, but the method did not.
+            Decision::NoMatch(request) if !request.parts.allowed_methods.is_empty() => {
+                Either::Right(Either::Left(
+                    route::NotFound
+                        .match_request(request, &self.state)
+                        .await
+                        .unwrap(),
+                ))
+            }
+            Decision::NoMatch(request) => match self.redirect(&request.parts) {
+                Some(redirect) => Either::Right(Either::Left(redirect)),
+                // It is safe to unwrap here, the fallback always matches.
+                None => Either::Right(Either::Right(
+                    self.fallback
+                        .match_request(request, &self.state)
+                        .await
+                        .unwrap(),
+                )),
+            },
+        }
+        .into_response();
+
+        let keep_alive =
+            write_response(&mut writer, write_buf, response, version, head, keep_alive).await?;
+        if !keep_alive {
+            return Ok(Connection::Close);
+        }
+
+        // Drain what the handler did not read from the request body,
+        // the next request starts right after it.
+        let mut body = Body::new(&mut body_state, body_buf, &mut reader);
+        let mut scratch = [0u8; 64];
+        loop {
+            match body.read(&mut scratch).await {
+                Ok(0) => break,
+                Ok(_) => continue,
+                Err(BodyError::Io(err)) => return Err(ServiceError::Io(err)),
+                Err(BodyError::InvalidChunk) => {
+                    return Err(ServiceError::ProtocolError(ProtocolError::InvalidChunk))
                 }
-                Err(err) => return Err(ServiceError::ProtocolError(ProtocolError::Parser(err))),
+                // The client closed the connection, there is no next request.
+                Err(BodyError::UnexpectedEof) => return Ok(Connection::Close),
             }
+        }
+        if !body_state.is_complete() {
+            return Ok(Connection::Close);
+        }
+
+        let pipelined = body_state.pipelined();
+        Ok(Connection::KeepAlive {
+            pipelined: body_start + pipelined.start..body_start + pipelined.end,
+        })
+    }
+
+    /// Matches the request with the routes, which may match its first segment.
+    ///
+    /// A `HEAD` request which no `HEAD` route matched, is matched again as a `GET` request
+    /// if a route of its path has a `GET` handler, the server omits the body. The handler
+    /// still sees the `HEAD` method.
+    async fn match_route<'a, B: Read>(
+        &'a self,
+        mut request: Request<'a, B, ()>,
+    ) -> Decision<'a, R::Response, B, ()> {
+        request.parts.route_candidates = self.table.candidates(request.parts.route_path);
+        match self.route.match_request(request, &self.state).await {
+            Decision::NoMatch(mut request)
+                if request.parts.route_method == http::Method::HEAD
+                    && request.parts.allowed_methods.contains(http::Method::GET) =>
+            {
+                request.parts.route_method = http::Method::GET;
+                match self.route.match_request(request, &self.state).await {
+                    Decision::NoMatch(mut request) => {
+                        request.parts.route_method = http::Method::HEAD;
+                        Decision::NoMatch(request)
+                    }
+                    decision => decision,
+                }
+            }
+            decision => decision,
+        }
+    }
+
+    /// Redirects a path to its form with or without a trailing slash, if that form
+    /// matches a route and the router is configured with [`TrailingSlash::Redirect`].
+    ///
+    /// Without the `alloc` feature, the `Location` must fit into
+    /// [`MAX_HEADER_VALUE_LEN`](http::MAX_HEADER_VALUE_LEN) bytes,
+    /// otherwise the request is handled by the fallback.
+    fn redirect(&self, parts: &Parts<'_, ()>) -> Option<Response<&'static [u8]>> {
+        if self.config.trailing_slash != TrailingSlash::Redirect
+            || !parts.allowed_methods.is_empty()
+        {
+            return None;
+        }
+
+        let path = parts.route_path;
+        let target = match path.strip_suffix('/') {
+            Some("") => return None,
+            Some(path) => http::HeaderValue::from_display(path),
+            None if path.starts_with('/') => {
+                http::HeaderValue::from_display(format_args!("{path}/"))
+            }
+            None => return None,
+        }
+        .ok()?;
+        if !self.route.matches_path(target.as_str(), parts) {
+            return None;
+        }
+
+        let location = match parts.query {
+            Some(query) => {
+                http::HeaderValue::from_display(format_args!("{target}?{query}")).ok()?
+            }
+            None => target,
         };
+        let mut response = (http::StatusCode::PERMANENT_REDIRECT, ()).into_response();
+        let _ = response.headers.insert(
+            const { http::HeaderName::from_static("Location") },
+            location,
+        );
+        Some(response)
+    }
 
-        let paq = PathAndQuery::parse(path)
-            .map_err(ProtocolError::InvalidUrl)
-            .map_err(ServiceError::ProtocolError)?;
-        let parts = Parts {
-            method: http::Method::new(method)
-                .map_err(ProtocolError::InvalidMethod)
-                .map_err(ServiceError::ProtocolError)?,
-            path: paq.path(),
-            query: paq.query(),
-            headers: Headers { headers, buf: &buf },
-            extracted_path: (),
+    /// Answers a malformed request with an error response and closes the connection.
+    ///
+    /// The protocol error is always returned, even if the error response could not be written.
+    async fn reject<Wr: Write, BE>(
+        &self,
+        writer: Wr,
+        buf: &mut [u8],
+        err: ProtocolError,
+    ) -> Result<Connection, ServiceError<Wr::Error, BE>> {
+        let response = (self.config.error_response)(&err);
+        let _ = write_response(writer, buf, response, 1, false, false).await;
+
+        Err(ServiceError::ProtocolError(err))
+    }
+}
+
+/// Returns the range of `value` within `buf`, which it was parsed from.
+fn range_of(buf: &[u8], value: &str) -> core::ops::Range<usize> {
+    let start = value.as_ptr() as usize - buf.as_ptr() as usize;
+    start..start + value.len()
+}
+
+/// Panics in debug builds if a route of `new` covers a route of `existing` with a
+/// common method, which would then be unreachable.
+fn assert_no_conflicts<S>(new: &impl Route<S>, existing: &impl Route<S>) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    new.visit_routes(&Pattern::new(), &mut |pattern, methods| {
+        existing.visit_routes(&Pattern::new(), &mut |existing, existing_methods| {
+            let common = methods.intersection(existing_methods);
+            if pattern.covers(existing) && !common.is_empty() {
+                panic!("route `{common} {pattern}` conflicts with the existing route `{existing}`");
+            }
+        });
+    });
+}
+
+/// Collects the methods of all routes, which panics if they do not fit into a
+/// [`MethodSet`](http::MethodSet) or into the value of an `Allow` header.
+///
+/// The methods allowed for a request are a subset of them, collecting them never
+/// fails while serving a request.
+fn assert_methods_fit<S>(route: &impl Route<S>) {
+    let mut methods = http::MethodSet::new();
+    route.allowed_methods(&mut methods);
+    methods.insert(http::Method::OPTIONS);
+    assert!(
+        http::HeaderValue::from_display(&methods).is_ok(),
+        "the methods of the routes do not fit into an `Allow` header without the `alloc` feature"
+    );
+}
+
+/// Returns the path with its trailing slash removed or added, if it did not match any route.
+///
+/// A path with a slash added is stored in `appended`, without the `alloc` feature it
+/// must fit into [`MAX_HEADER_VALUE_LEN`](http::MAX_HEADER_VALUE_LEN) bytes.
+/// The root path is never stripped.
+fn other_form<'a>(
+    parts: &Parts<'a, ()>,
+    appended: &'a mut Option<http::HeaderValue>,
+) -> Option<&'a str> {
+    if !parts.allowed_methods.is_empty() {
+        return None;
+    }
+
+    let path = parts.route_path;
+    match path.strip_suffix('/') {
+        Some("") => None,
+        Some(path) => Some(path),
+        None if path.starts_with('/') => {
+            let path = http::HeaderValue::from_display(format_args!("{path}/")).ok()?;
+            Some(appended.insert(path).as_str())
+        }
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::convert::Infallible;
+    use std::{string::String, vec::Vec};
+
+    use super::*;
+    use crate::{
+        extract::{Host, Path, State},
+        FromRequestParts, Segment, StrSegment,
+    };
+
+    /// Reader which returns the input in the given chunks, one chunk per read at most.
+    struct ChunkedReader<'a> {
+        chunks: Vec<&'a [u8]>,
+        /// Amount of reads, including the final read at the end of the input.
+        reads: usize,
+    }
+
+    impl<'a> ChunkedReader<'a> {
+        fn split_at(input: &'a [u8], mid: usize) -> Self {
+            let (a, b) = input.split_at(mid);
+            Self {
+                chunks: [a, b].into_iter().filter(|c| !c.is_empty()).collect(),
+                reads: 0,
+            }
+        }
+    }
+
+    impl ErrorType for ChunkedReader<'_> {
+        type Error = Infallible;
+    }
+
+    impl Read for ChunkedReader<'_> {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            self.reads += 1;
+            let Some(chunk) = self.chunks.first_mut() else {
+                return Ok(0);
+            };
+
+            let len = buf.len().min(chunk.len());
+            buf[..len].copy_from_slice(&chunk[..len]);
+            *chunk = &chunk[len..];
+            if chunk.is_empty() {
+                self.chunks.remove(0);
+            }
+
+            Ok(len)
+        }
+    }
+
+    struct VecWriter(Vec<u8>);
+
+    impl ErrorType for VecWriter {
+        type Error = Infallible;
+    }
+
+    impl Write for VecWriter {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    async fn serve_split(service: &impl Service, input: &[u8], expected: &str) {
+        for mid in 0..=input.len() {
+            let mut writer = VecWriter(Vec::new());
+            service
+                .serve(ChunkedReader::split_at(input, mid), &mut writer)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                core::str::from_utf8(&writer.0).unwrap(),
+                expected,
+                "split at {mid}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_requests() {
+        let router = Router::new()
+            .post("/echo", |body: heapless::Vec<u8, 16>| async move { body })
+            .get("/", || async { "hi" });
+
+        serve_split(
+            &router,
+            b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello\
+              GET / HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello\
+             HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 2\r\nConnection: close\r\n\r\nhi",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_content_length() {
+        let router = Router::new()
+            .get("/empty", || async {})
+            .get("/text", || async { "hello" })
+            .get("/explicit", || async {
+                ([("Content-Length", "5")], "hello")
+            });
+
+        assert_eq!(
+            serve_get(&router, "/empty").await,
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        );
+        assert_eq!(
+            serve_get(&router, "/text").await,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 5\r\nConnection: close\r\n\r\nhello"
+        );
+        // A length set by the handler is not sent twice.
+        assert_eq!(
+            serve_get(&router, "/explicit").await,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 5\r\nConnection: close\r\n\r\nhello"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_content_length_without_body() {
+        let router = Router::new()
+            .get("/no-content", || async {
+                (http::StatusCode::NO_CONTENT, ())
+            })
+            .get("/not-modified", || async {
+                (http::StatusCode::NOT_MODIFIED, [("ETag", "\"1\"")], ())
+            });
+
+        assert_eq!(
+            serve_get(&router, "/no-content").await,
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n"
+        );
+        assert_eq!(
+            serve_get(&router, "/not-modified").await,
+            "HTTP/1.1 304 Not Modified\r\nETag: \"1\"\r\nConnection: close\r\n\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_body_is_written() {
+        let router = Router::new()
+            .get("/stream", || async {
+                Response {
+                    status_code: http::StatusCode::NO_CONTENT,
+                    headers: http::HeaderMap::new(),
+                    body: Stream(&[b"hello"]),
+                }
+            })
+            .get("/text", || async {
+                (http::StatusCode::NOT_MODIFIED, "hello")
+            });
+
+        // Neither chunked framing nor the body are sent, the connection is kept alive.
+        serve_split(
+            &router,
+            b"GET /stream HTTP/1.1\r\n\r\nGET /stream HTTP/1.0\r\nConnection: keep-alive\r\n\r\n\
+              GET /text HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 204 No Content\r\n\r\n\
+             HTTP/1.1 204 No Content\r\nConnection: keep-alive\r\n\r\n\
+             HTTP/1.1 304 Not Modified\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Connection: close\r\n\r\n",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_invalid_response_header() {
+        let router = Router::new()
+            .get("/name", || async {
+                ([("X-Injected\r\nSet-Cookie", "a")], "hello")
+            })
+            .get("/value", || async {
+                ([("X-Header", "a\r\nSet-Cookie: b")], "hello")
+            });
+
+        for path in ["/name", "/value"] {
+            assert_eq!(
+                serve_get(&router, path).await,
+                "HTTP/1.1 500 Internal Server Error\r\n\
+                 Content-Type: text/plain; charset=utf-8\r\n\
+                 Content-Length: 23\r\nConnection: close\r\n\r\nInvalid response header"
+            );
+        }
+    }
+
+    /// Response body of unknown length, which returns one chunk per read.
+    struct Stream(&'static [&'static [u8]]);
+
+    impl ErrorType for Stream {
+        type Error = Infallible;
+    }
+
+    impl Read for Stream {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let Some((chunk, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[..chunk.len()].copy_from_slice(chunk);
+            self.0 = rest;
+            Ok(chunk.len())
+        }
+    }
+
+    impl crate::response::Body for Stream {
+        fn size_hint(&self) -> crate::response::SizeHint {
+            crate::response::SizeHint::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunked_response() {
+        const CHUNKS: &[&[u8]] = &[b"hello", b"abcdefghijklmnopqrstuvwxyz", &[b'x'; 300]];
+        let router = Router::new().get("/", || async {
+            Response {
+                status_code: http::StatusCode::OK,
+                headers: http::HeaderMap::new(),
+                body: Stream(CHUNKS),
+            }
+        });
+        let x = "x".repeat(300);
+
+        serve_split(
+            &router,
+            b"GET / HTTP/1.1\r\n\r\nHEAD / HTTP/1.1\r\nConnection: close\r\n\r\n",
+            &std::format!(
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                 5\r\nhello\r\n1a\r\nabcdefghijklmnopqrstuvwxyz\r\n12c\r\n{x}\r\n0\r\n\r\n\
+                 HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n"
+            ),
+        )
+        .await;
+
+        // HTTP/1.0 does not support chunking, the end of the body is signaled by closing
+        // the connection.
+        serve_split(
+            &router,
+            b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n",
+            &std::format!(
+                "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nhelloabcdefghijklmnopqrstuvwxyz{x}"
+            ),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive() {
+        const HI: &str = "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+                          Content-Length: 2\r\n";
+        let router = Router::new().get("/", || async { "hi" });
+
+        // The connection is closed after the client asks for it.
+        serve_split(
+            &router,
+            b"GET / HTTP/1.1\r\nConnection: close\r\n\r\nGET / HTTP/1.1\r\n\r\n",
+            &std::format!("{HI}Connection: close\r\n\r\nhi"),
+        )
+        .await;
+
+        // HTTP/1.0 connections are only kept alive on request.
+        serve_split(
+            &router,
+            b"GET / HTTP/1.0\r\n\r\nGET / HTTP/1.0\r\n\r\n",
+            &std::format!("{HI}Connection: close\r\n\r\nhi"),
+        )
+        .await;
+        serve_split(
+            &router,
+            b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET / HTTP/1.0\r\n\r\n",
+            &std::format!("{HI}Connection: keep-alive\r\n\r\nhi{HI}Connection: close\r\n\r\nhi"),
+        )
+        .await;
+
+        // The last request allowed on the connection is answered with `Connection: close`.
+        let router = router.with_config(ServerConfig {
+            max_requests_per_connection: 2,
+            ..ServerConfig::new()
+        });
+        serve_split(
+            &router,
+            b"GET / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n",
+            &std::format!("{HI}\r\nhi{HI}Connection: close\r\n\r\nhi"),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_unread_body_is_drained() {
+        let router = Router::new()
+            .post("/ignore", || async { "ignored" })
+            .get("/", || async { "hi" });
+
+        serve_split(
+            &router,
+            b"POST /ignore HTTP/1.1\r\nContent-Length: 7\r\n\r\nGET / x\
+              GET / HTTP/1.1\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 7\r\n\r\nignored\
+             HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 2\r\n\r\nhi",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_truncated_body() {
+        let router = Router::new().post("/echo", |body: heapless::Vec<u8, 32>| async move { body });
+
+        serve_split(
+            &router,
+            b"POST /echo HTTP/1.1\r\nContent-Length: 10\r\n\r\nhel",
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 33\r\n\r\nFailed to buffer the request body",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_body_is_read_once() {
+        let router = Router::new().post("/echo", |body: heapless::Vec<u8, 32>| async move { body });
+
+        serve_split(
+            &router,
+            b"POST /echo HTTP/1.0\r\nContent-Length: 11\r\n\r\nhello world",
+            "HTTP/1.1 200 OK\r\nContent-Length: 11\r\nConnection: close\r\n\r\nhello world",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_chunked_request_body() {
+        let router = Router::new()
+            .post("/echo", |body: heapless::Vec<u8, 32>| async move { body })
+            .get("/", || async { "hi" });
+
+        serve_split(
+            &router,
+            b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+              5;name=value\r\nhello\r\n6\r\n world\r\n0\r\nTrailer: yes\r\n\r\n\
+              GET / HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world\
+             HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 2\r\nConnection: close\r\n\r\nhi",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_truncated_chunked_body() {
+        let router = Router::new().post("/echo", |body: heapless::Vec<u8, 32>| async move { body });
+
+        for body in [&b"10\r\nhel"[..], b"5\r\nhello\r\n", b"5\r\nhello\r\n0\r\n"] {
+            let mut input = b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+            input.extend_from_slice(body);
+            serve_split(
+                &router,
+                &input,
+                "HTTP/1.1 500 Internal Server Error\r\nContent-Type: text/plain; charset=utf-8\r\n\
+                 Content-Length: 33\r\n\r\nFailed to buffer the request body",
+            )
+            .await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunked_framing_is_buffered() {
+        let router = Router::new()
+            .post("/echo", |body: heapless::Vec<u8, 32>| async move { body })
+            .get("/", || async { "hi" });
+
+        let head = b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n";
+        let body =
+            b"1\r\na\r\n1\r\nb\r\n1\r\nc\r\n0\r\n\r\nGET / HTTP/1.1\r\nConnection: close\r\n\r\n";
+        let mut reader = ChunkedReader {
+            chunks: std::vec![&head[..], &body[..]],
+            reads: 0,
         };
+        let mut writer = VecWriter(Vec::new());
+        router.serve(&mut reader, &mut writer).await.unwrap();
 
-        let content_length = parts
-            .headers
-            .get_first("Content-Length")
-            .and_then(|value| value.parse::<usize>().ok())
-            .unwrap_or(0);
+        assert_eq!(
+            String::from_utf8(writer.0).unwrap(),
+            "HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nabc\
+             HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 2\r\nConnection: close\r\n\r\nhi"
+        );
+        // The body and the pipelined request are read at once.
+        assert_eq!(reader.reads, 2);
+    }
 
-        let body = Body::new(content_length, &buf[body_start..pos], reader);
-        let request = Request::from_parts(parts, body);
+    #[tokio::test]
+    async fn test_unread_chunked_body_is_drained() {
+        let router = Router::new()
+            .post("/ignore", || async { "ignored" })
+            .get("/", || async { "hi" });
+
+        serve_split(
+            &router,
+            b"POST /ignore HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+              7\r\nGET / x\r\n0\r\n\r\n\
+              GET / HTTP/1.1\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 7\r\n\r\nignored\
+             HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 2\r\n\r\nhi",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_method_router() {
+        let router = Router::new().route(
+            "/",
+            route::get(|| async { "get" })
+                .post(|| async { "post" })
+                .on(http::Method::from_static("PROPFIND"), || async {
+                    "propfind"
+                }),
+        );
+
+        for (method, body) in [("GET", "get"), ("POST", "post"), ("PROPFIND", "propfind")] {
+            let input = std::format!("{method} / HTTP/1.1\r\nConnection: close\r\n\r\n");
+            serve_split(
+                &router,
+                input.as_bytes(),
+                &std::format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                ),
+            )
+            .await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_method_not_allowed() {
+        let router = Router::new()
+            .route(
+                "/",
+                route::get(|| async { "get" }).post(|| async { "post" }),
+            )
+            .put("/", || async { "put" })
+            .get("/other", || async { "other" });
+
+        serve_split(
+            &router,
+            b"DELETE / HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 405 Method Not Allowed\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Allow: GET, HEAD, POST, PUT, OPTIONS\r\nContent-Length: 18\r\nConnection: close\r\n\r\n\
+             Method Not Allowed",
+        )
+        .await;
+        serve_split(
+            &router,
+            b"DELETE /missing HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 9\r\nConnection: close\r\n\r\nNot Found",
+        )
+        .await;
+    }
+
+    /// The method of the request, as seen by the handler.
+    struct RequestMethod(&'static str);
+
+    impl<'a, S, P> FromRequestParts<'a, S, P> for RequestMethod {
+        type Rejection = Infallible;
+
+        async fn from_request_parts(
+            parts: &mut Parts<'a, P>,
+            _state: &S,
+        ) -> Result<Self, Infallible> {
+            Ok(Self(match parts.method == http::Method::HEAD {
+                true => "HEAD",
+                false => "GET",
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_head_uses_get_handler() {
+        let router = Router::new()
+            .get("/", |RequestMethod(method)| async move {
+                ([("X-Method", method)], "hello")
+            })
+            .route(
+                "/explicit",
+                route::get(|| async { "get" }).head(|| async { ([("X-Head", "yes")], "head") }),
+            )
+            // A `HEAD` route takes precedence over a `GET` route added later.
+            .head("/separate", || async { ([("X-Head", "yes")], "head") })
+            .get("/separate", || async { "get" });
+
+        serve_split(
+            &router,
+            b"HEAD / HTTP/1.1\r\n\r\nHEAD /explicit HTTP/1.1\r\n\r\n\
+              HEAD /separate HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nX-Method: HEAD\r\n\
+             Content-Length: 5\r\n\r\n\
+             HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nX-Head: yes\r\n\
+             Content-Length: 4\r\n\r\n\
+             HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nX-Head: yes\r\n\
+             Content-Length: 4\r\n\r\n\
+             HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nX-Method: GET\r\n\
+             Content-Length: 5\r\nConnection: close\r\n\r\nhello",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_options() {
+        let router = Router::new()
+            .get("/", || async { "get" })
+            .post("/", || async { "post" })
+            .route(
+                "/explicit",
+                route::get(|| async { "get" }).options(|| async { "options" }),
+            )
+            .put("/other", || async { "put" });
+
+        for (target, allow) in [
+            ("/", "GET, HEAD, POST, OPTIONS"),
+            ("*", "GET, HEAD, POST, PUT, OPTIONS"),
+        ] {
+            let input = std::format!("OPTIONS {target} HTTP/1.1\r\nConnection: close\r\n\r\n");
+            serve_split(
+                &router,
+                input.as_bytes(),
+                &std::format!(
+                    "HTTP/1.1 200 OK\r\nAllow: {allow}\r\n\
+                     Content-Length: 0\r\nConnection: close\r\n\r\n"
+                ),
+            )
+            .await;
+        }
+        serve_split(
+            &router,
+            b"OPTIONS /explicit HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 7\r\nConnection: close\r\n\r\noptions",
+        )
+        .await;
+    }
+
+    struct FullPath(heapless::String<32>);
+
+    impl<'a, S, P> FromRequestParts<'a, S, P> for FullPath {
+        type Rejection = Infallible;
+
+        async fn from_request_parts(
+            parts: &mut Parts<'a, P>,
+            _state: &S,
+        ) -> Result<Self, Infallible> {
+            Ok(Self(parts.path.try_into().unwrap()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback() {
+        let router = Router::new()
+            .get("/", || async { "root" })
+            .fallback(|FullPath(path)| async move { (http::StatusCode::NOT_FOUND, path) });
+
+        serve_split(
+            &router,
+            b"GET /missing HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 8\r\nConnection: close\r\n\r\n/missing",
+        )
+        .await;
+        serve_split(
+            &router,
+            b"POST / HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 405 Method Not Allowed\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Allow: GET, HEAD, OPTIONS\r\nContent-Length: 18\r\nConnection: close\r\n\r\n\
+             Method Not Allowed",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_rest_segment() {
+        struct StaticFiles;
+
+        impl<'p, S> Route<S, ((), &'p str)> for StaticFiles {
+            type Response = &'static str;
+
+            async fn match_request<'a, Body: Read>(
+                &'a self,
+                req: Request<'a, Body, ((), &'p str)>,
+                _state: &S,
+            ) -> Decision<'a, Self::Response, Body, ((), &'p str)> {
+                match req.extracted_path().1 {
+                    "" => Decision::Match("index"),
+                    "css/main.css" => Decision::Match("main"),
+                    _ => Decision::NoMatch(req),
+                }
+            }
+        }
+
+        let router = Router::new().route(("static", crate::Rest), StaticFiles);
+
+        for (path, expected) in [
+            ("/static/", "200 OK\r\n"),
+            ("/static/css/main.css", "200 OK\r\n"),
+            ("/static/css/other.css", "404 Not Found\r\n"),
+            ("/static", "404 Not Found\r\n"),
+        ] {
+            let input = std::format!("GET {path} HTTP/1.1\r\nConnection: close\r\n\r\n");
+            let mut writer = VecWriter(Vec::new());
+            router
+                .serve(ChunkedReader::split_at(input.as_bytes(), 0), &mut writer)
+                .await
+                .unwrap();
 
-        let response = self
-            .route
-            .match_request(request, &self.state)
+            let response = String::from_utf8(writer.0).unwrap();
+            assert!(
+                response.starts_with(&std::format!("HTTP/1.1 {expected}")),
+                "{path}: {response}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rest_in_handler() {
+        let router = Router::new()
+            .get(
+                ("static", crate::Rest),
+                async |Path((_, rest)): Path<((), &str)>| match rest {
+                    "" => "index",
+                    "css/main.css" => "main",
+                    _ => "other",
+                },
+            )
+            .get(
+                ("users", StrSegment, "files", crate::Rest),
+                async |Path((_, user, _, file)): Path<((), &str, (), &str)>| {
+                    assert_eq!(user, "alice");
+                    if file == "a/b.txt" {
+                        "file"
+                    } else {
+                        "other"
+                    }
+                },
+            );
+
+        for (path, body) in [
+            ("/static/", "index"),
+            ("/static/css/main.css", "main"),
+            ("/static/img/logo.png", "other"),
+            ("/users/alice/files/a/b.txt", "file"),
+        ] {
+            let response = serve_get(&router, path).await;
+            assert!(
+                response.starts_with("HTTP/1.1 200 OK\r\n"),
+                "{path}: {response}"
+            );
+            assert!(
+                response.ends_with(&std::format!("\r\n\r\n{body}")),
+                "{path}: {response}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_borrowed_captures() {
+        async fn greet(Path((_, name)): Path<((), &str)>) -> heapless::String<16> {
+            let mut greeting = heapless::String::new();
+            greeting.push_str("hello ").unwrap();
+            greeting.push_str(name).unwrap();
+            greeting
+        }
+
+        let router = Router::new().get(("users", StrSegment), greet).get(
+            ("users", StrSegment, "files", StrSegment),
+            async |Path((_, user, _, file)): Path<((), &str, (), &str)>| {
+                assert_eq!(user, "alice");
+                if file == "notes.txt" {
+                    "notes"
+                } else {
+                    "other"
+                }
+            },
+        );
+
+        assert!(serve_get(&router, "/users/alice")
+            .await
+            .ends_with("\r\n\r\nhello alice"));
+        assert!(serve_get(&router, "/users/alice/files/notes.txt")
             .await
-            // It is safe to unwrap here, we always have a `NotFound` fallback handler.
-            .unwrap()
-            .into_response();
+            .ends_with("\r\n\r\nnotes"));
+    }
+
+    #[tokio::test]
+    async fn test_merge() {
+        fn sensors<S>() -> Router<S, impl Route<S>, (), impl Sized> {
+            Router::new()
+                .get("/temperature", || async { "21" })
+                .post("/temperature", || async { "set" })
+        }
+
+        fn diagnostics<S>() -> Router<S, impl Route<S>, (), impl Sized> {
+            Router::new().get("/uptime", || async { "42" })
+        }
+
+        let router = Router::new()
+            .get("/", || async { "root" })
+            .merge(sensors())
+            .merge(diagnostics());
+
+        for (request, status) in [
+            ("GET /", "200 OK"),
+            ("GET /temperature", "200 OK"),
+            ("POST /temperature", "200 OK"),
+            ("GET /uptime", "200 OK"),
+            ("POST /uptime", "405 Method Not Allowed"),
+            ("GET /missing", "404 Not Found"),
+        ] {
+            let input = std::format!("{request} HTTP/1.1\r\nConnection: close\r\n\r\n");
+            let mut writer = VecWriter(Vec::new());
+            router
+                .serve(ChunkedReader::split_at(input.as_bytes(), 0), &mut writer)
+                .await
+                .unwrap();
+
+            let response = String::from_utf8(writer.0).unwrap();
+            assert!(
+                response.starts_with(&std::format!("HTTP/1.1 {status}\r\n")),
+                "{request}: {response}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nest() {
+        #[derive(Clone)]
+        struct ApiState(&'static str);
+
+        impl FromRef<&'static str> for ApiState {
+            fn from_ref(input: &&'static str) -> Self {
+                Self(input)
+            }
+        }
+
+        let users = Router::<ApiState, _>::new()
+            .get(
+                "/",
+                |State(ApiState(name)): State<ApiState>| async move { name },
+            )
+            .get(
+                ("users", u32::segment()),
+                |Path((_, _id)): Path<((), u32)>, FullPath(path)| async move { path },
+            );
+        let router = Router::new()
+            .get("/api/v10", || async { "not nested" })
+            .nest("/api/v1", users)
+            .with_state("state");
+
+        for (path, body) in [
+            ("/api/v1", "state"),
+            ("/api/v1/users/7", "/api/v1/users/7"),
+            ("/api/v10", "not nested"),
+        ] {
+            let input = std::format!("GET {path} HTTP/1.1\r\nConnection: close\r\n\r\n");
+            let mut writer = VecWriter(Vec::new());
+            router
+                .serve(ChunkedReader::split_at(input.as_bytes(), 0), &mut writer)
+                .await
+                .unwrap();
 
-        use utils::{WriteExt, WriteFmtError};
-        write!(writer, "HTTP/1.1 {}\r\n", response.status_code())
+            let response = String::from_utf8(writer.0).unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+            assert!(
+                response.ends_with(&std::format!("\r\n\r\n{body}")),
+                "{response}"
+            );
+        }
+
+        let mut writer = VecWriter(Vec::new());
+        router
+            .serve(
+                ChunkedReader::split_at(b"GET /api/v1/users/x HTTP/1.1\r\n\r\n", 0),
+                &mut writer,
+            )
             .await
-            .map_err(|err| match err {
-                WriteFmtError::FmtError => unreachable!("internal format buffer too small"),
-                WriteFmtError::Other(err) => ServiceError::Io(err),
-            })?;
-        writer
-            .write_all(b"Connection: Close\r\n")
+            .unwrap();
+        assert!(writer.0.starts_with(b"HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_path_deserialize() {
+        #[derive(serde::Deserialize)]
+        struct Params {
+            id: u32,
+            post: heapless::String<8>,
+        }
+
+        let router = Router::new()
+            .get(
+                crate::path!("/users/{id}/posts/{post}"),
+                |Path(params): Path<Params>| async move {
+                    assert_eq!(params.id, 7);
+                    params.post
+                },
+            )
+            .get(
+                crate::path!("/pairs/{a}/{b}"),
+                |Path((a, b)): Path<(u8, u8)>| async move {
+                    assert_eq!((a, b), (1, 2));
+                    "pair"
+                },
+            )
+            .get(
+                crate::path!("/single/{id}"),
+                |Path(id): Path<u32>| async move {
+                    assert_eq!(id, 3);
+                    "single"
+                },
+            );
+
+        for (path, status, body) in [
+            ("/users/7/posts/hello", "200 OK", "hello"),
+            ("/pairs/1/2", "200 OK", "pair"),
+            ("/single/3", "200 OK", "single"),
+            (
+                "/users/x/posts/hello",
+                "400 Bad Request",
+                "Invalid URL: Cannot parse `id` as `u32`",
+            ),
+            (
+                "/pairs/1/300",
+                "400 Bad Request",
+                "Invalid URL: Cannot parse `b` as `u8`",
+            ),
+            ("/users/7/posts/h%C3%A9", "200 OK", "hé"),
+            (
+                "/users/7/posts/a%2Fb",
+                "400 Bad Request",
+                "Invalid URL: Cannot decode `post`: Encoded slash in path segment",
+            ),
+        ] {
+            let input = std::format!("GET {path} HTTP/1.1\r\nConnection: close\r\n\r\n");
+            let mut writer = VecWriter(Vec::new());
+            router
+                .serve(ChunkedReader::split_at(input.as_bytes(), 0), &mut writer)
+                .await
+                .unwrap();
+
+            let response = String::from_utf8(writer.0).unwrap();
+            assert!(
+                response.starts_with(&std::format!("HTTP/1.1 {status}\r\n")),
+                "{response}"
+            );
+            assert!(
+                response.ends_with(&std::format!("\r\n\r\n{body}")),
+                "{response}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_percent_encoded_path() {
+        let router = Router::new()
+            .get(
+                ("files", heapless::String::<16>::segment()),
+                |Path((_, name)): Path<((), heapless::String<16>)>| async move { name },
+            )
+            .get("/my file", || async { "literal" });
+
+        for (path, status, body) in [
+            ("/files/my%20file", "200 OK", "my file"),
+            ("/my%20file", "200 OK", "literal"),
+            (
+                "/files/a%2Fb",
+                "400 Bad Request",
+                "Encoded slash in path segment",
+            ),
+            (
+                "/files/a%zz",
+                "400 Bad Request",
+                "Invalid percent-encoding in path",
+            ),
+        ] {
+            let input = std::format!("GET {path} HTTP/1.1\r\nConnection: close\r\n\r\n");
+            let mut writer = VecWriter(Vec::new());
+            router
+                .serve(ChunkedReader::split_at(input.as_bytes(), 0), &mut writer)
+                .await
+                .unwrap();
+
+            let response = String::from_utf8(writer.0).unwrap();
+            assert!(
+                response.starts_with(&std::format!("HTTP/1.1 {status}\r\n")),
+                "{response}"
+            );
+            assert!(
+                response.ends_with(&std::format!("\r\n\r\n{body}")),
+                "{response}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trailing_slash() {
+        let router = || {
+            Router::new()
+                .get("/foo", || async { "foo" })
+                .get("/bar/", || async { "bar" })
+                .post("/baz", || async { "baz" })
+                .get(crate::path!("/users/{id: u32}"), || async { "user" })
+        };
+
+        let strict = router();
+        assert!(serve_get(&strict, "/foo/")
             .await
-            .map_err(ServiceError::Io)?;
-        writer.write_all(b"\r\n").await.map_err(ServiceError::Io)?;
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
 
-        let mut body = response.into_body();
-        loop {
-            let mut buf = [0; 1024];
-            let len = body.read(&mut buf).await.map_err(ServiceError::Body)?;
-            if len == 0 {
-                break;
+        let redirect = router().with_config(ServerConfig {
+            trailing_slash: TrailingSlash::Redirect,
+            ..ServerConfig::new()
+        });
+        let response = serve_get(&redirect, "/foo/?a=1").await;
+        assert!(response.starts_with("HTTP/1.1 308 Permanent Redirect\r\n"));
+        assert!(
+            response.contains("\r\nLocation: /foo?a=1\r\n"),
+            "{response}"
+        );
+        assert!(serve_get(&redirect, "/bar/").await.ends_with("\r\n\r\nbar"));
+        let response = serve_get(&redirect, "/bar").await;
+        assert!(response.starts_with("HTTP/1.1 308 Permanent Redirect\r\n"));
+        assert!(response.contains("\r\nLocation: /bar/\r\n"), "{response}");
+        // Neither form of the path matches a route.
+        assert!(serve_get(&redirect, "/qux/")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(serve_get(&redirect, "/users/x/")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(serve_get(&redirect, "/users/1/")
+            .await
+            .starts_with("HTTP/1.1 308 Permanent Redirect\r\n"));
+        // The path matched, only the method did not.
+        assert!(serve_get(&redirect, "/baz")
+            .await
+            .starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(serve_get(&redirect, "/")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        let ignore = router().with_config(ServerConfig {
+            trailing_slash: TrailingSlash::Ignore,
+            ..ServerConfig::new()
+        });
+        assert!(serve_get(&ignore, "/foo/").await.ends_with("\r\n\r\nfoo"));
+        assert!(serve_get(&ignore, "/foo").await.ends_with("\r\n\r\nfoo"));
+        assert!(serve_get(&ignore, "/bar/").await.ends_with("\r\n\r\nbar"));
+        assert!(serve_get(&ignore, "/bar").await.ends_with("\r\n\r\nbar"));
+        assert!(serve_get(&ignore, "/users/1/")
+            .await
+            .ends_with("\r\n\r\nuser"));
+        // Neither form of the path matches a route.
+        assert!(serve_get(&ignore, "/qux/")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(serve_get(&ignore, "/qux")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(serve_get(&ignore, "/baz")
+            .await
+            .starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_merge_slashes() {
+        struct Query(heapless::String<32>);
+
+        impl<'a, S, P> FromRequestParts<'a, S, P> for Query {
+            type Rejection = Infallible;
+
+            async fn from_request_parts(
+                parts: &mut Parts<'a, P>,
+                _state: &S,
+            ) -> Result<Self, Infallible> {
+                Ok(Self(parts.query.unwrap_or_default().try_into().unwrap()))
+            }
+        }
+
+        let router = Router::new()
+            .get(
+                ("a", u32::segment(), "b"),
+                |FullPath(mut path), Query(query)| async move {
+                    path.push(' ').unwrap();
+                    path.push_str(&query).unwrap();
+                    path
+                },
+            )
+            .get("/", || async { "root" })
+            .with_config(ServerConfig {
+                merge_slashes: true,
+                ..ServerConfig::new()
+            });
+
+        let response = serve_get(&router, "//a///7/b?x=//y").await;
+        assert!(response.ends_with("\r\n\r\n/a/7/b x=//y"), "{response}");
+        assert!(serve_get(&router, "///").await.ends_with("\r\n\r\nroot"));
+    }
+
+    #[tokio::test]
+    async fn test_first_segment_dispatch() {
+        let api = Router::<(), _>::new()
+            .get("/status", || async { "status" })
+            .get(("status", u32::segment()), || async { "code" });
+        let merged = Router::<(), _>::new().get("/merged", || async { "merged" });
+        let router = Router::new()
+            .get("/users", || async { "users" })
+            .get(("users", u32::segment()), || async { "user" })
+            .post("/posts", || async { "posts" })
+            .get("/my file", || async { "file" })
+            .nest("/api", api)
+            .merge(merged)
+            .get(
+                crate::path!("/{name: heapless::String<8>}/about"),
+                |Path((name,)): Path<(heapless::String<8>,)>| async move { name },
+            )
+            // Newer routes take precedence, regardless of their first segment.
+            .get(("users", "me"), || async { "me" });
+
+        for (path, body) in [
+            ("/users", "users"),
+            ("/users/1", "user"),
+            ("/users/me", "me"),
+            ("/my%20file", "file"),
+            ("/api/status", "status"),
+            ("/api/status/1", "code"),
+            ("/merged", "merged"),
+            ("/users/about", "users"),
+            ("/api/about", "api"),
+        ] {
+            let response = serve_get(&router, path).await;
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+            assert!(
+                response.ends_with(&std::format!("\r\n\r\n{body}")),
+                "{response}"
+            );
+        }
+
+        assert!(serve_get(&router, "/posts")
+            .await
+            .starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(serve_get(&router, "/users/x")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(serve_get(&router, "/api")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(serve_get(&router, "/%zz/about")
+            .await
+            .starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+        // Routes are numbered from the newest, only the second one starts with a capture.
+        let users = router.table.candidates("/users/1");
+        assert_eq!(router.table.candidates("/us%65rs"), users);
+        assert!(users.any_of_first(1));
+        assert!(users.skip(1).any_of_first(1));
+        assert!(!users.skip(2).any_of_first(4));
+        assert!(users.skip(6).any_of_first(2));
+        let missing = router.table.candidates("/missing");
+        assert!(!missing.any_of_first(1));
+        assert!(missing.skip(1).any_of_first(1));
+        assert!(!missing.skip(2).any_of_first(64));
+        assert_eq!(router.table.candidates("/%zz"), Candidates::ALL);
+        assert_eq!(router.table.candidates("*"), Candidates::ALL);
+    }
+
+    #[test]
+    fn test_route_conflicts() {
+        let _ = Router::<(), _>::new()
+            .get("/users", || async { "users" })
+            .post("/users", || async { "created" })
+            .get(("users", u32::segment()), || async { "user" })
+            .get(("users", "me"), || async { "me" })
+            .get(("users", u32::segment(), crate::Rest), || async { "files" })
+            .get(("files", crate::Rest), || async { "files" })
+            .get(("files", "index.html"), || async { "index" })
+            .head("/about", || async { "about" })
+            .get("/about", || async { "about" })
+            .nest(
+                "/api",
+                Router::<(), _>::new().get("/users", || async { "api" }),
+            );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(
+        expected = "route `GET /users/{u32}` conflicts with the existing route `/users/{u32}`"
+    )]
+    fn test_conflicting_captures() {
+        let _ = Router::<(), _>::new()
+            .get(("users", u32::segment()), || async { "id" })
+            .get(crate::path!("/users/{id: u32}"), || async { "name" });
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(
+        expected = "route `GET /users/{&str}` conflicts with the existing route `/users/me`"
+    )]
+    fn test_covered_literal() {
+        let _ = Router::<(), _>::new()
+            .get(("users", "me"), || async { "me" })
+            .get(crate::path!("/users/{name}"), || async { "name" });
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(
+        expected = "route `GET /files/{*}` conflicts with the existing route `/files/a/b`"
+    )]
+    fn test_covered_rest() {
+        let _ = Router::<(), _>::new()
+            .get("/files/a/b", || async { "b" })
+            .get(("files", crate::Rest), || async { "files" });
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "route `POST /users` conflicts with the existing route `/users`")]
+    fn test_conflicting_literals() {
+        let _ = Router::<(), _>::new()
+            .route(
+                "/users",
+                route::get(|| async { "get" }).post(|| async { "post" }),
+            )
+            .post("/users", || async { "again" });
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "route `GET /api` conflicts with the existing route `/api`")]
+    fn test_conflicting_nested_root() {
+        let _ = Router::<(), _>::new()
+            .get("/api", || async { "api" })
+            .nest("/api", Router::<(), _>::new().get("/", || async { "root" }));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "`GET` is already routed")]
+    fn test_duplicate_method() {
+        let _ =
+            Router::<(), _>::new().route("/", route::get(|| async { "a" }).get(|| async { "b" }));
+    }
+
+    /// Routes `/a` and `/b` with five extension methods, more than fit into
+    /// a method set without `alloc`.
+    fn extension_methods() -> Router<(), impl Route<()>, (), impl Sized> {
+        let method = http::Method::from_static;
+        Router::new()
+            .route(
+                "/a",
+                route::on(method("A1"), || async {}).on(method("A2"), || async {}),
+            )
+            .route(
+                "/b",
+                route::on(method("B1"), || async {})
+                    .on(method("B2"), || async {})
+                    .on(method("B3"), || async {}),
+            )
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    #[test]
+    #[should_panic(expected = "a method set holds at most 4 extension methods")]
+    fn test_too_many_extension_methods() {
+        let _ = extension_methods();
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    #[test]
+    #[should_panic(expected = "the methods of the routes do not fit into an `Allow` header")]
+    fn test_allow_header_too_long() {
+        let _ = Router::<(), _>::new()
+            .route(
+                "/",
+                route::get(|| async {})
+                    .post(|| async {})
+                    .put(|| async {})
+                    .delete(|| async {})
+                    .patch(|| async {}),
+            )
+            .route(
+                "/",
+                route::on(http::Method::CONNECT, || async {})
+                    .on(http::Method::TRACE, || async {})
+                    .on(http::Method::from_static("PROPFIND"), || async {}),
+            );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[tokio::test]
+    async fn test_many_extension_methods() {
+        let router = extension_methods();
+
+        serve_split(
+            &router,
+            b"OPTIONS * HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nAllow: OPTIONS, B1, B2, B3, A1, A2\r\n\
+             Content-Length: 0\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_host() {
+        let device = Router::<(), _>::new()
+            .get("/", |Host(host): Host| async move { host })
+            .get("/status", || async { "ok" });
+        let router = Router::new()
+            .get("/", || async { "portal" })
+            .get("/status", || async { "portal status" })
+            .host("device.local", device)
+            .host(
+                "*.example.com:8080",
+                Router::<(), _>::new().get("/", || async { "example" }),
+            );
+
+        let serve = |target: &'static str, host: Option<&'static str>| {
+            let host = host.map_or(String::new(), |host| std::format!("Host: {host}\r\n"));
+            let input = std::format!("GET {target} HTTP/1.1\r\n{host}Connection: close\r\n\r\n");
+            let router = &router;
+            async move {
+                let mut writer = VecWriter(Vec::new());
+                router
+                    .serve(ChunkedReader::split_at(input.as_bytes(), 0), &mut writer)
+                    .await
+                    .unwrap();
+                String::from_utf8(writer.0).unwrap()
+            }
+        };
+
+        for (target, host, body) in [
+            ("/", Some("device.local"), "device.local"),
+            ("/", Some("DEVICE.local:80"), "DEVICE.local:80"),
+            ("/status", Some("device.local"), "ok"),
+            ("/", Some("captive.portal"), "portal"),
+            ("/status", None, "portal status"),
+            ("/", Some("a.example.com:8080"), "example"),
+            ("/", Some("a.example.com"), "portal"),
+            // The absolute-form takes precedence over the `Host` header.
+            ("http://device.local/status", Some("captive.portal"), "ok"),
+            ("http://user@device.local", None, "device.local"),
+            ("HTTP://a.example.com:8080/?q", None, "example"),
+        ] {
+            let response = serve(target, host).await;
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+            assert!(
+                response.ends_with(&std::format!("\r\n\r\n{body}")),
+                "{response}"
+            );
+        }
+
+        let missing = Router::<(), _>::new().get("/", |Host(_): Host<16>| async { "host" });
+        assert!(serve_get(&missing, "/")
+            .await
+            .starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(serve_get(&missing, "http://device.local//")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(serve_get(&missing, "http://device.local.example.com/")
+            .await
+            .starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+        let merged = missing.with_config(ServerConfig {
+            merge_slashes: true,
+            ..ServerConfig::new()
+        });
+        assert!(serve_get(&merged, "http://device.local//")
+            .await
+            .ends_with("\r\n\r\nhost"));
+    }
+
+    #[tokio::test]
+    async fn test_guards() {
+        use crate::guard::{self, Guard};
+
+        let router =
+            Router::new()
+                .route("/items", route::post(|| async { "form" }))
+                .route(
+                    "/items",
+                    route::post(|| async { "json" }).guard(guard::content_type("application/json")),
+                )
+                .route(
+                    "/items",
+                    route::post(|| async { "debug" }).guard(guard::query_has("debug").and(
+                        guard::header("X-Debug", "1").or(|parts: &Parts<'_, ()>| {
+                            parts.headers.get_first("X-Trace").is_some()
+                        }),
+                    )),
+                )
+                .route(
+                    "/admin",
+                    route::get(|| async { "admin" }).guard(guard::header("X-Admin", "yes")),
+                );
+
+        let serve = |target: &'static str, headers: &'static str| {
+            let input = std::format!(
+                "POST {target} HTTP/1.1\r\n{headers}Content-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+            let router = &router;
+            async move {
+                let mut writer = VecWriter(Vec::new());
+                router
+                    .serve(ChunkedReader::split_at(input.as_bytes(), 0), &mut writer)
+                    .await
+                    .unwrap();
+                String::from_utf8(writer.0).unwrap()
             }
-            writer
-                .write_all(&buf[..len])
+        };
+
+        for (target, headers, body) in [
+            ("/items", "", "form"),
+            ("/items", "Content-Type: application/json\r\n", "json"),
+            (
+                "/items",
+                "Content-Type: Application/JSON; charset=utf-8\r\n",
+                "json",
+            ),
+            ("/items", "Content-Type: text/plain\r\n", "form"),
+            ("/items?debug", "X-Debug: 1\r\n", "debug"),
+            ("/items?a=b&debug=", "X-Trace: 7\r\n", "debug"),
+            ("/items?debug", "X-Debug: 2\r\n", "form"),
+            ("/items?debugging", "X-Debug: 1\r\n", "form"),
+        ] {
+            let response = serve(target, headers).await;
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+            assert!(
+                response.ends_with(&std::format!("\r\n\r\n{body}")),
+                "{response}"
+            );
+        }
+
+        assert!(serve_get(&router, "/admin")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found\r\n"));
+        let input = b"GET /admin HTTP/1.1\r\nX-Admin: yes\r\nConnection: close\r\n\r\n";
+        let mut writer = VecWriter(Vec::new());
+        router
+            .serve(ChunkedReader::split_at(input, 0), &mut writer)
+            .await
+            .unwrap();
+        assert!(writer.0.ends_with(b"\r\n\r\nadmin"));
+    }
+
+    async fn serve_get(service: &impl Service, path: &str) -> String {
+        let input = std::format!("GET {path} HTTP/1.1\r\nConnection: close\r\n\r\n");
+        let mut writer = VecWriter(Vec::new());
+        service
+            .serve(ChunkedReader::split_at(input.as_bytes(), 0), &mut writer)
+            .await
+            .unwrap();
+        String::from_utf8(writer.0).unwrap()
+    }
+
+    async fn serve_error(service: &impl Service, input: &[u8]) -> (ProtocolError, String) {
+        let mut writer = VecWriter(Vec::new());
+        let result = service
+            .serve(ChunkedReader::split_at(input, 0), &mut writer)
+            .await;
+
+        let Err(ServiceError::ProtocolError(err)) = result else {
+            panic!("expected a protocol error");
+        };
+        (err, String::from_utf8(writer.0).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_framing() {
+        let router = Router::new().post("/", || async { "hi" });
+
+        let (err, response) = serve_error(
+            &router,
+            b"POST / HTTP/1.1\r\nContent-Length: 3\r\n\
+              Transfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+        )
+        .await;
+
+        assert!(matches!(err, ProtocolError::ConflictingFraming));
+        assert_eq!(
+            response,
+            "HTTP/1.1 400 Bad Request\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 11\r\nConnection: close\r\n\r\nBad Request"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_protocol_error_status() {
+        let router = Router::new().post("/", || async { "hi" });
+
+        for (input, status) in [
+            (
+                &b"GET / HTTP/2.0\r\n\r\n"[..],
+                "505 HTTP Version Not Supported",
+            ),
+            (b"GET /\x7f HTTP/1.1\r\n\r\n", "400 Bad Request"),
+            (
+                b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n",
+                "501 Not Implemented",
+            ),
+            (
+                b"POST / HTTP/1.1\r\nContent-Length: 1, 2\r\n\r\n",
+                "400 Bad Request",
+            ),
+            (
+                b"POST / HTTP/1.1\r\nContent-Length: +3\r\n\r\nabc",
+                "400 Bad Request",
+            ),
+            (b"GET * HTTP/1.1\r\n\r\n", "400 Bad Request"),
+        ] {
+            let (_, response) = serve_error(&router, input).await;
+            assert!(response.starts_with(&std::format!("HTTP/1.1 {status}\r\n")));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_error_response() {
+        let router = Router::new()
+            .post("/", || async { "hi" })
+            .with_config(ServerConfig {
+                error_response: |err| (err.status_code(), "custom").into_response(),
+                ..ServerConfig::new()
+            });
+
+        let (_, response) = serve_error(&router, b"GET / HTTP/2.0\r\n\r\n").await;
+        assert!(response.ends_with("\r\n\r\ncustom"));
+    }
+
+    #[tokio::test]
+    async fn test_small_buffers() {
+        let router = Router::new().post("/echo", |body: heapless::Vec<u8, 64>| async move { body });
+
+        let input = b"POST /echo HTTP/1.1\r\nContent-Length: 26\r\n\r\nabcdefghijklmnopqrstuvwxyz";
+        for mid in 0..=input.len() {
+            let mut writer = VecWriter(Vec::new());
+            let mut buffers = Buffers::<64, 2, 4>::new();
+            router
+                .serve_with_buffers(
+                    &mut buffers,
+                    ChunkedReader::split_at(input, mid),
+                    &mut writer,
+                )
                 .await
-                .map_err(ServiceError::Io)?;
+                .unwrap();
+
+            assert_eq!(
+                String::from_utf8(writer.0).unwrap(),
+                "HTTP/1.1 200 OK\r\nContent-Length: 26\r\n\r\nabcdefghijklmnopqrstuvwxyz"
+            );
         }
 
-        Ok(())
+        // The headers fill the entire buffer, there is no space to buffer the chunk framing.
+        let input = b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                      3\r\nabc\r\n0\r\n\r\n";
+        for mid in 0..=input.len() {
+            let mut writer = VecWriter(Vec::new());
+            let mut buffers = Buffers::<51, 2, 4>::new();
+            router
+                .serve_with_buffers(
+                    &mut buffers,
+                    ChunkedReader::split_at(input, mid),
+                    &mut writer,
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(
+                String::from_utf8(writer.0).unwrap(),
+                "HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nabc"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_header_buffer() {
+        let router = Router::new().get("/", || async { "hi" });
+
+        // Header indices are not limited to 16 bits.
+        let input = std::format!("GET / HTTP/1.1\r\n{}: value\r\n\r\n", "a".repeat(70_000));
+        let mut writer = VecWriter(Vec::new());
+        let mut buffers = std::boxed::Box::new(Buffers::<100_000, 4, 64>::new());
+        router
+            .serve_with_buffers(
+                &mut buffers,
+                ChunkedReader::split_at(input.as_bytes(), 0),
+                &mut writer,
+            )
+            .await
+            .unwrap();
+
+        assert!(writer.0.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_buffer_full() {
+        let router = Router::new().get("/", || async { "hi" });
+
+        for (input, expected) in [
+            (
+                &b"GET /aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa HTTP/1.1\r\n\r\n"[..],
+                "414 URI Too Long",
+            ),
+            (
+                b"GET / HTTP/1.1\r\nCookie: aaaaaaaaaaaaaaaaaaaaaaa\r\n\r\n",
+                "431 Request Header Fields Too Large",
+            ),
+            (
+                b"GET / HTTP/1.1\r\nA: a\r\nB: b\r\nC: c\r\n\r\n",
+                "431 Request Header Fields Too Large",
+            ),
+        ] {
+            let mut writer = VecWriter(Vec::new());
+            let mut buffers = Buffers::<32, 2, 16>::new();
+            let result = router
+                .serve_with_buffers(&mut buffers, ChunkedReader::split_at(input, 0), &mut writer)
+                .await;
+
+            assert!(matches!(result, Err(ServiceError::ProtocolError(_))));
+            let response = String::from_utf8(writer.0).unwrap();
+            assert!(response.starts_with(&std::format!("HTTP/1.1 {expected}\r\n")));
+        }
     }
 }